@@ -0,0 +1,572 @@
+// ------------------------------------ Database archive (JSON / ZIP) -----------------------------------------
+//
+// Export complet de la base dans un fichier JSON versionné, pour passer d'une machine à l'autre.
+// À l'import, le mode "merge" ajoute les données à la base existante en renumérotant les ids,
+// le mode "replace" vide d'abord les tables et conserve les ids d'origine.
+//
+// L'archive portable (.zip) contient en plus tous les fichiers référencés par les enregistrements,
+// avec un manifeste et leurs sommes de contrôle ; à l'import les fichiers sont restaurés dans le
+// coffre de documents et les chemins réécrits en références du coffre.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+use sha2::{Digest, Sha256};
+use tauri::command;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::attachments::migrate_legacy_attachments;
+use crate::retention::remove_unreferenced_files;
+use crate::{backfill_task_dates, hash_file};
+use crate::versions::migrate_document_versions;
+use crate::vault::{file_name_of, resolve_file_reference, split_paths, store_in_vault, vault_year, FILE_COLUMNS, VAULT_DIR};
+
+const ARCHIVE_FORMAT: &str = "admin-manager-archive";
+// À incrémenter quand une évolution du schéma rend les anciennes archives incompatibles
+const ARCHIVE_VERSION: u32 = 1;
+
+pub(crate) struct ArchiveTable {
+    name: &'static str,
+    // Tables de référence (créanciers, catégories...) : fusionnées par nom en mode "merge"
+    lookup: bool,
+    // Colonnes contenant l'id d'une ligne d'une autre table (ou de la même), à renuméroter à l'import
+    references: &'static [(&'static str, &'static str)],
+    // Colonnes (table, id) désignant une ligne de n'importe quelle table : les pièces jointes
+    owner: Option<(&'static str, &'static str)>,
+    // Règles sans clé unique : en mode "merge", une ligne identique à une ligne existante n'est pas recopiée
+    skip_identical: bool,
+}
+
+const fn lookup_table(name: &'static str) -> ArchiveTable {
+    ArchiveTable { name, lookup: true, references: &[], owner: None, skip_identical: false }
+}
+
+const fn record_table(name: &'static str, references: &'static [(&'static str, &'static str)]) -> ArchiveTable {
+    ArchiveTable { name, lookup: false, references, owner: None, skip_identical: false }
+}
+
+const fn rule_table(name: &'static str) -> ArchiveTable {
+    ArchiveTable { name, lookup: false, references: &[], owner: None, skip_identical: true }
+}
+
+// Ordre d'import : une table doit venir après celles qu'elle référence
+pub(crate) const ARCHIVE_TABLES: &[ArchiveTable] = &[
+    lookup_table("creditors"),
+    lookup_table("concerns"),
+    lookup_table("categories"),
+    lookup_table("sources"),
+    lookup_table("revenue_types"),
+    lookup_table("admin_documents_concerns"),
+    lookup_table("admin_documents_categories"),
+    lookup_table("task_categories"),
+    lookup_table("task_priorities"),
+    lookup_table("task_statuses"),
+    rule_table("invoice_extraction_rules"),
+    rule_table("retention_rules"),
+    rule_table("inbox_rules"),
+    record_table("audit_log", &[]),
+    record_table("invoices", &[]),
+    record_table("revenues", &[]),
+    record_table("tasks", &[("parent_id", "tasks"), ("next_occurrence_id", "tasks")]),
+    record_table("task_checklist_items", &[("task_id", "tasks")]),
+    record_table("task_dependencies", &[("task_id", "tasks"), ("depends_on_id", "tasks")]),
+    record_table("task_reminders", &[("task_id", "tasks")]),
+    record_table("admin_documents", &[("renewal_task_id", "tasks")]),
+    record_table("admin_document_versions", &[("admin_document_id", "admin_documents")]),
+    ArchiveTable { name: "attachments", lookup: false, references: &[], owner: Some(("record_type", "record_id")), skip_identical: false },
+];
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct DatabaseArchive {
+    format: String,
+    schema_version: u32,
+    exported_at: String,
+    pub(crate) tables: BTreeMap<String, Vec<Map<String, JsonValue>>>,
+}
+
+#[derive(Serialize)]
+pub struct ArchiveSummary {
+    path: String,
+    rows: BTreeMap<String, usize>,
+}
+
+#[derive(Serialize)]
+pub struct RestoreSummary {
+    mode: String,
+    imported: BTreeMap<String, usize>,
+    // Lignes de tables de référence déjà présentes (mode "merge")
+    merged: BTreeMap<String, usize>,
+    ignored_tables: Vec<String>,
+}
+
+fn to_json(value: Value) -> Result<JsonValue, String> {
+    Ok(match value {
+        Value::Null => JsonValue::Null,
+        Value::Integer(i) => JsonValue::from(i),
+        Value::Real(r) => JsonValue::from(r),
+        Value::Text(t) => JsonValue::String(t),
+        Value::Blob(_) => return Err("BLOB columns are not supported in archives".to_string()),
+    })
+}
+
+fn to_sql(value: &JsonValue) -> Value {
+    match value {
+        JsonValue::Null => Value::Null,
+        JsonValue::Bool(b) => Value::Integer(*b as i64),
+        JsonValue::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        JsonValue::String(s) => Value::Text(s.clone()),
+        other => Value::Text(other.to_string()),
+    }
+}
+
+// Colonnes de la table, avec leur contrainte NOT NULL
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<(String, bool)>, String> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table)).map_err(|e| e.to_string())?;
+    let column_iter = stmt.query_map([], |row| Ok((row.get::<_, String>(1)?, row.get::<_, bool>(3)?))).map_err(|e| e.to_string())?;
+
+    let mut columns = Vec::new();
+    for column in column_iter {
+        columns.push(column.map_err(|e| e.to_string())?);
+    }
+    Ok(columns)
+}
+
+pub(crate) fn build_archive(conn: &Connection) -> Result<DatabaseArchive, String> {
+    let mut tables = BTreeMap::new();
+    for table in ARCHIVE_TABLES {
+        let mut stmt = conn.prepare(&format!("SELECT * FROM {} ORDER BY id", table.name)).map_err(|e| e.to_string())?;
+        let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+        let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
+
+        let mut records = Vec::new();
+        while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+            let mut record = Map::new();
+            for (i, column) in columns.iter().enumerate() {
+                let value: Value = row.get(i).map_err(|e| e.to_string())?;
+                record.insert(column.clone(), to_json(value)?);
+            }
+            records.push(record);
+        }
+        tables.insert(table.name.to_string(), records);
+    }
+
+    Ok(DatabaseArchive {
+        format: ARCHIVE_FORMAT.to_string(),
+        schema_version: ARCHIVE_VERSION,
+        exported_at: chrono::Local::now().naive_local().to_string(),
+        tables,
+    })
+}
+
+pub(crate) fn restore_archive(conn: &mut Connection, archive: &DatabaseArchive, mode: &str) -> Result<RestoreSummary, String> {
+    if archive.format != ARCHIVE_FORMAT {
+        return Err("This file is not an Admin Manager archive".to_string());
+    }
+    if archive.schema_version > ARCHIVE_VERSION {
+        return Err(format!(
+            "Archive schema version {} is newer than the supported version {}",
+            archive.schema_version, ARCHIVE_VERSION
+        ));
+    }
+    let replace = match mode {
+        "replace" => true,
+        "merge" => false,
+        other => return Err(format!("Unknown import mode: {}", other)),
+    };
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    if replace {
+        for table in ARCHIVE_TABLES.iter().rev() {
+            tx.execute(&format!("DELETE FROM {}", table.name), []).map_err(|e| e.to_string())?;
+        }
+    }
+
+    let mut id_maps: HashMap<&str, HashMap<i64, i64>> = HashMap::new();
+    let mut imported = BTreeMap::new();
+    let mut merged = BTreeMap::new();
+
+    for table in ARCHIVE_TABLES {
+        let rows = match archive.tables.get(table.name) {
+            Some(rows) => rows,
+            None => continue,
+        };
+        let columns_info = table_columns(&tx, table.name)?;
+        let known_columns: Vec<&str> = columns_info.iter().map(|(name, _)| name.as_str()).collect();
+        let required_columns: Vec<&str> = columns_info.iter().filter(|(_, not_null)| *not_null).map(|(name, _)| name.as_str()).collect();
+        let mut id_map = HashMap::new();
+        // Références vers une ligne de la même table (sous-tâches), reprises une fois la table importée
+        let mut self_references = Vec::new();
+        let mut imported_count = 0;
+        let mut merged_count = 0;
+
+        'rows: for row in rows {
+            let old_id = row.get("id").and_then(JsonValue::as_i64);
+
+            // Une ligne rattachée à un enregistrement absent de l'archive est ignorée
+            let mut owner_id = None;
+            if let Some((type_column, id_column)) = table.owner {
+                let owner_table = row.get(type_column).and_then(JsonValue::as_str).unwrap_or_default();
+                let old_owner = row.get(id_column).and_then(JsonValue::as_i64).unwrap_or_default();
+                match id_maps.get(owner_table).and_then(|m| m.get(&old_owner)) {
+                    Some(new) => owner_id = Some((id_column, *new)),
+                    None => continue,
+                }
+            }
+
+            if !replace && table.lookup {
+                let name = row.get("name").and_then(JsonValue::as_str).unwrap_or_default();
+                let existing: Option<i64> = tx
+                    .query_row(&format!("SELECT id FROM {} WHERE name = ?1", table.name), params![name], |r| r.get(0))
+                    .ok();
+                if let (Some(existing), Some(old_id)) = (existing, old_id) {
+                    id_map.insert(old_id, existing);
+                    merged_count += 1;
+                    continue;
+                }
+            }
+
+            let mut columns = Vec::new();
+            let mut values = Vec::new();
+            let mut pending = Vec::new();
+            for (column, value) in row {
+                if !known_columns.contains(&column.as_str()) || (column == "id" && !replace) {
+                    continue;
+                }
+                let mut value = to_sql(value);
+                if let Some((_, new)) = owner_id.filter(|(c, _)| c == column) {
+                    value = Value::Integer(new);
+                }
+                if let Some((_, target)) = table.references.iter().find(|(c, _)| c == column) {
+                    if let Value::Integer(old) = value {
+                        if *target == table.name {
+                            pending.push((column.as_str(), old));
+                        }
+                        value = match id_maps.get(target).and_then(|m| m.get(&old)) {
+                            Some(new) => Value::Integer(*new),
+                            // Ligne rattachée à une ligne absente de l'archive (ou ignorée) : ignorée à son tour
+                            None if *target != table.name && required_columns.contains(&column.as_str()) => continue 'rows,
+                            None => Value::Null,
+                        };
+                    }
+                }
+                columns.push(column.as_str());
+                values.push(value);
+            }
+
+            if !replace && table.skip_identical {
+                let conditions: Vec<String> = columns.iter().enumerate().map(|(i, c)| format!("{} IS ?{}", c, i + 1)).collect();
+                let existing: Option<i64> = tx
+                    .query_row(
+                        &format!("SELECT id FROM {} WHERE {} LIMIT 1", table.name, conditions.join(" AND ")),
+                        params_from_iter(values.iter()),
+                        |r| r.get(0),
+                    )
+                    .optional()
+                    .map_err(|e| format!("{}: {}", table.name, e))?;
+                if let Some(existing) = existing {
+                    if let Some(old_id) = old_id {
+                        id_map.insert(old_id, existing);
+                    }
+                    merged_count += 1;
+                    continue;
+                }
+            }
+
+            let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("?{}", i)).collect();
+            tx.execute(
+                &format!("INSERT INTO {} ({}) VALUES ({})", table.name, columns.join(", "), placeholders.join(", ")),
+                params_from_iter(values.iter()),
+            ).map_err(|e| format!("{}: {}", table.name, e))?;
+
+            let new_id = tx.last_insert_rowid();
+            if let Some(old_id) = old_id {
+                id_map.insert(old_id, new_id);
+            }
+            self_references.extend(pending.into_iter().map(|(column, old)| (column, old, new_id)));
+            imported_count += 1;
+        }
+
+        for (column, old, new_id) in self_references {
+            tx.execute(
+                &format!("UPDATE {} SET {} = ?1 WHERE id = ?2", table.name, column),
+                params![id_map.get(&old), new_id],
+            ).map_err(|e| format!("{}: {}", table.name, e))?;
+        }
+
+        id_maps.insert(table.name, id_map);
+        imported.insert(table.name.to_string(), imported_count);
+        merged.insert(table.name.to_string(), merged_count);
+    }
+
+    // Les archives antérieures à la table des pièces jointes n'ont que les anciennes colonnes
+    migrate_legacy_attachments(&tx).map_err(|e| e.to_string())?;
+    migrate_document_versions(&tx).map_err(|e| e.to_string())?;
+    backfill_task_dates(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    let ignored_tables = archive.tables
+        .keys()
+        .filter(|name| !ARCHIVE_TABLES.iter().any(|t| t.name == name.as_str()))
+        .cloned()
+        .collect();
+
+    Ok(RestoreSummary { mode: mode.to_string(), imported, merged, ignored_tables })
+}
+
+#[command]
+pub fn export_database(path: String) -> Result<ArchiveSummary, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let archive = build_archive(&conn)?;
+
+    let file = File::create(&path).map_err(|e| e.to_string())?;
+    serde_json::to_writer_pretty(BufWriter::new(file), &archive).map_err(|e| e.to_string())?;
+
+    let rows = archive.tables.iter().map(|(name, rows)| (name.clone(), rows.len())).collect();
+    Ok(ArchiveSummary { path, rows })
+}
+
+#[command]
+pub fn import_database(path: String, mode: String) -> Result<RestoreSummary, String> {
+    let file = File::open(&path).map_err(|e| e.to_string())?;
+    let archive: DatabaseArchive = serde_json::from_reader(BufReader::new(file)).map_err(|e| e.to_string())?;
+
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    restore_archive(&mut conn, &archive, &mode)
+}
+
+// ---------- Archive portable
+
+const PORTABLE_FORMAT: &str = "admin-manager-portable-archive";
+const PORTABLE_VERSION: u32 = 1;
+const MANIFEST_ENTRY: &str = "manifest.json";
+const DATABASE_ENTRY: &str = "database.json";
+// Dossier temporaire d'extraction, dans le coffre pour que le rangement final soit un simple déplacement
+const STAGING_DIR: &str = ".staging";
+// Colonnes de fichiers de l'archive : celles des enregistrements, les pièces jointes et les versions de documents
+const ARCHIVE_FILE_COLUMNS: &[(&str, &str, bool, Option<&str>)] = &[
+    ("attachments", "file_reference", false, Some("added_date")),
+    ("admin_document_versions", "file_reference", false, Some("uploaded_date")),
+];
+
+#[derive(Serialize, Deserialize)]
+struct PortableManifest {
+    format: String,
+    version: u32,
+    created_at: String,
+    database_sha256: String,
+    files: Vec<ManifestFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ManifestFile {
+    original_path: String,
+    archive_path: String,
+    sha256: String,
+    size: u64,
+}
+
+#[derive(Serialize)]
+pub struct PortableArchiveSummary {
+    path: String,
+    rows: BTreeMap<String, usize>,
+    files: usize,
+    missing_files: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct PortableRestoreSummary {
+    database: RestoreSummary,
+    restored_files: usize,
+}
+
+// Copie une entrée de l'archive vers `writer` en vérifiant sa somme de contrôle
+fn copy_verified<R: Read, W: Write>(reader: &mut R, writer: &mut W, expected_sha256: &str, name: &str) -> Result<(), String> {
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer).map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        writer.write_all(&buffer[..read]).map_err(|e| e.to_string())?;
+    }
+    if format!("{:x}", hasher.finalize()) != expected_sha256 {
+        return Err(format!("Checksum mismatch for {}, the archive is corrupted", name));
+    }
+    Ok(())
+}
+
+#[command]
+pub fn export_portable_archive(path: String) -> Result<PortableArchiveSummary, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let archive = build_archive(&conn)?;
+
+    let database = serde_json::to_vec_pretty(&archive).map_err(|e| e.to_string())?;
+    let mut manifest = PortableManifest {
+        format: PORTABLE_FORMAT.to_string(),
+        version: PORTABLE_VERSION,
+        created_at: chrono::Local::now().naive_local().to_string(),
+        database_sha256: format!("{:x}", Sha256::digest(&database)),
+        files: Vec::new(),
+    };
+
+    let file = File::create(&path).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    let options = SimpleFileOptions::default();
+
+    zip.start_file(DATABASE_ENTRY, options).map_err(|e| e.to_string())?;
+    zip.write_all(&database).map_err(|e| e.to_string())?;
+
+    // Chaque fichier n'est stocké qu'une fois, même s'il est référencé par plusieurs enregistrements
+    let mut stored: HashMap<String, String> = HashMap::new();
+    let mut missing_files = Vec::new();
+    for (table, column, multi, _) in FILE_COLUMNS.iter().chain(ARCHIVE_FILE_COLUMNS) {
+        for row in archive.tables.get(*table).into_iter().flatten() {
+            let value = row.get(*column).and_then(JsonValue::as_str).unwrap_or_default();
+            for original_path in split_paths(value, *multi) {
+                if stored.contains_key(original_path) || missing_files.iter().any(|m| m == original_path) {
+                    continue;
+                }
+                let source_path = resolve_file_reference(original_path)?;
+                if !source_path.is_file() {
+                    missing_files.push(original_path.to_string());
+                    continue;
+                }
+
+                let sha256 = hash_file(&source_path)?;
+                let archive_path = format!("files/{}/{}", sha256, file_name_of(original_path));
+                if !manifest.files.iter().any(|f| f.archive_path == archive_path) {
+                    zip.start_file(archive_path.as_str(), options).map_err(|e| e.to_string())?;
+                    let mut source = File::open(&source_path).map_err(|e| e.to_string())?;
+                    io::copy(&mut source, &mut zip).map_err(|e| e.to_string())?;
+                }
+                manifest.files.push(ManifestFile {
+                    original_path: original_path.to_string(),
+                    archive_path: archive_path.clone(),
+                    sha256,
+                    size: fs::metadata(&source_path).map_err(|e| e.to_string())?.len(),
+                });
+                stored.insert(original_path.to_string(), archive_path);
+            }
+        }
+    }
+
+    zip.start_file(MANIFEST_ENTRY, options).map_err(|e| e.to_string())?;
+    serde_json::to_writer_pretty(&mut zip, &manifest).map_err(|e| e.to_string())?;
+    zip.finish().map_err(|e| e.to_string())?.flush().map_err(|e| e.to_string())?;
+
+    let rows = archive.tables.iter().map(|(name, rows)| (name.clone(), rows.len())).collect();
+    Ok(PortableArchiveSummary { path, rows, files: manifest.files.len(), missing_files })
+}
+
+#[command]
+pub fn import_portable_archive(path: String, mode: String) -> Result<PortableRestoreSummary, String> {
+    let file = File::open(&path).map_err(|e| e.to_string())?;
+    let mut zip = ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+
+    let manifest: PortableManifest = {
+        let entry = zip.by_name(MANIFEST_ENTRY).map_err(|_| "This file is not an Admin Manager portable archive".to_string())?;
+        serde_json::from_reader(entry).map_err(|e| e.to_string())?
+    };
+    if manifest.format != PORTABLE_FORMAT {
+        return Err("This file is not an Admin Manager portable archive".to_string());
+    }
+    if manifest.version > PORTABLE_VERSION {
+        return Err(format!(
+            "Portable archive version {} is newer than the supported version {}",
+            manifest.version, PORTABLE_VERSION
+        ));
+    }
+
+    let mut database = Vec::new();
+    {
+        let mut entry = zip.by_name(DATABASE_ENTRY).map_err(|e| e.to_string())?;
+        copy_verified(&mut entry, &mut database, &manifest.database_sha256, DATABASE_ENTRY)?;
+    }
+    let mut archive: DatabaseArchive = serde_json::from_slice(&database).map_err(|e| e.to_string())?;
+
+    // Extraction des fichiers : <coffre>/.staging/<sha256>/<nom d'origine>
+    let staging = Path::new(VAULT_DIR).join(STAGING_DIR);
+    let mut extracted: HashMap<String, String> = HashMap::new();
+    for file in &manifest.files {
+        if file.sha256.len() != 64 || !file.sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("Invalid checksum in manifest for {}", file.archive_path));
+        }
+        let directory = staging.join(&file.sha256);
+        fs::create_dir_all(&directory).map_err(|e| e.to_string())?;
+        let destination = directory.join(file_name_of(&file.original_path));
+
+        if !destination.is_file() {
+            let mut entry = zip.by_name(&file.archive_path).map_err(|e| e.to_string())?;
+            let mut output = File::create(&destination).map_err(|e| e.to_string())?;
+            if let Err(e) = copy_verified(&mut entry, &mut output, &file.sha256, &file.archive_path) {
+                let _ = fs::remove_dir_all(&staging);
+                return Err(e);
+            }
+        }
+        extracted.insert(file.original_path.clone(), destination.to_string_lossy().to_string());
+    }
+
+    // Rangement dans le coffre et réécriture des chemins avant l'import des données
+    // Un même fichier garde la même référence dans l'ancienne colonne et dans la table des pièces jointes
+    let mut vaulted: HashMap<String, String> = HashMap::new();
+    let rewritten_paths = (|| -> Result<(), String> {
+        for (table, column, multi, date_column) in FILE_COLUMNS.iter().chain(ARCHIVE_FILE_COLUMNS) {
+            for row in archive.tables.get_mut(*table).into_iter().flatten() {
+                let value = match row.get(*column).and_then(JsonValue::as_str) {
+                    Some(value) => value.to_string(),
+                    None => continue,
+                };
+                let year = vault_year(date_column.and_then(|c| row.get(c)).and_then(JsonValue::as_str));
+                // Les pièces jointes et les versions sont rangées avec le type de leur enregistrement
+                let record_type = match row.get("record_type").and_then(JsonValue::as_str) {
+                    Some(record_type) => record_type,
+                    None if *table == "admin_document_versions" => "admin_documents",
+                    None => table,
+                }.to_string();
+
+                let mut rewritten = Vec::new();
+                for original_path in split_paths(&value, *multi) {
+                    match (vaulted.get(original_path), extracted.get(original_path)) {
+                        (Some(reference), _) => rewritten.push(reference.clone()),
+                        (None, Some(staged)) => {
+                            let reference = store_in_vault(staged, &record_type, year, false)?;
+                            vaulted.insert(original_path.to_string(), reference.clone());
+                            rewritten.push(reference);
+                        }
+                        (None, None) => rewritten.push(original_path.to_string()),
+                    }
+                }
+                if !rewritten.is_empty() {
+                    row.insert(column.to_string(), JsonValue::String(rewritten.join(";")));
+                }
+            }
+        }
+        Ok(())
+    })();
+    if staging.exists() {
+        fs::remove_dir_all(&staging).map_err(|e| e.to_string())?;
+    }
+
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    match rewritten_paths.and_then(|_| restore_archive(&mut conn, &archive, &mode)) {
+        Ok(database) => Ok(PortableRestoreSummary { database, restored_files: vaulted.len() }),
+        Err(e) => {
+            // Données non restaurées : les fichiers rangés pour elles ne sont référencés nulle part
+            remove_unreferenced_files(&conn, vaulted.into_values().collect())?;
+            Err(e)
+        }
+    }
+}
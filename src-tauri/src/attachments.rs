@@ -0,0 +1,398 @@
+// ------------------------------------ Attachments -----------------------------------------
+//
+// Table unique des pièces jointes de tous les enregistrements (factures, revenus, documents, tâches).
+// Les anciennes colonnes (path, revenue_path, admin_doc_path, attachments) restent remplies avec la
+// première pièce jointe (ou la liste complète pour les tâches) pour les vues qui les lisent encore.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::command;
+
+use crate::hash_file;
+use crate::vault::{file_name_of, is_vault_reference, resolve_file_reference, split_paths, store_in_vault, vault_year, FILE_COLUMNS};
+
+#[derive(Serialize)]
+pub struct Attachment {
+    id: i64,
+    record_type: String,
+    record_id: i64,
+    file_reference: String,
+    original_name: String,
+    mime_type: String,
+    size: Option<i64>,
+    sha256: Option<String>,
+    added_date: String,
+    position: i64,
+}
+
+const ATTACHMENT_COLUMNS: &str = "id, record_type, record_id, file_reference, original_name, mime_type, size, sha256, added_date, position";
+
+fn attachment_from_row(row: &rusqlite::Row) -> rusqlite::Result<Attachment> {
+    Ok(Attachment {
+        id: row.get(0)?,
+        record_type: row.get(1)?,
+        record_id: row.get(2)?,
+        file_reference: row.get(3)?,
+        original_name: row.get(4)?,
+        mime_type: row.get(5)?,
+        size: row.get(6)?,
+        sha256: row.get(7)?,
+        added_date: row.get(8)?,
+        position: row.get(9)?,
+    })
+}
+
+fn legacy_column(record_type: &str) -> Result<(&'static str, &'static str, bool), String> {
+    FILE_COLUMNS
+        .iter()
+        .find(|(table, ..)| *table == record_type)
+        .map(|(table, column, multi, _)| (*table, *column, *multi))
+        .ok_or_else(|| format!("Unsupported record type: {}", record_type))
+}
+
+fn check_record_exists(conn: &Connection, table: &str, record_id: i64) -> Result<(), String> {
+    conn.query_row(&format!("SELECT id FROM {} WHERE id = ?1", table), params![record_id], |row| row.get::<_, i64>(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .map(|_| ())
+        .ok_or_else(|| format!("{} {} not found", table, record_id))
+}
+
+// Type MIME, taille et hash d'un fichier référencé ; taille et hash restent vides s'il n'est pas lisible
+fn file_details(file_reference: &str, original_name: &str) -> (String, Option<i64>, Option<String>) {
+    let path = resolve_file_reference(file_reference).ok();
+    let size = path.as_ref().and_then(|p| fs::metadata(p).ok()).map(|m| m.len() as i64);
+    let sha256 = path.as_ref().filter(|p| p.is_file()).and_then(|p| hash_file(p).ok());
+    let mime_type = mime_guess::from_path(original_name).first_or_octet_stream().to_string();
+    (mime_type, size, sha256)
+}
+
+// Enregistre une pièce jointe déjà rangée (ou simple nom de fichier pour les anciennes tâches)
+pub(crate) fn insert_attachment(
+    conn: &Connection,
+    record_type: &str,
+    record_id: i64,
+    file_reference: &str,
+    original_name: &str,
+    added_date: &str
+) -> rusqlite::Result<i64> {
+    let (mime_type, size, sha256) = file_details(file_reference, original_name);
+    conn.execute(
+        "INSERT INTO attachments (record_type, record_id, file_reference, original_name, mime_type, size, sha256, added_date, position)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, (SELECT COALESCE(MAX(position) + 1, 0) FROM attachments WHERE record_type = ?1 AND record_id = ?2))",
+        params![record_type, record_id, file_reference, original_name, mime_type, size, sha256, added_date],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+// Remplace le fichier de la première pièce jointe d'un enregistrement (ou l'ajoute s'il n'en a pas)
+pub(crate) fn replace_primary_attachment(
+    conn: &Connection,
+    record_type: &str,
+    record_id: i64,
+    file_reference: &str,
+    original_name: &str,
+    added_date: &str
+) -> Result<(), String> {
+    let primary: Option<i64> = conn.query_row(
+        "SELECT id FROM attachments WHERE record_type = ?1 AND record_id = ?2 ORDER BY position, id LIMIT 1",
+        params![record_type, record_id],
+        |row| row.get(0),
+    ).optional().map_err(|e| e.to_string())?;
+
+    match primary {
+        Some(id) => {
+            let (mime_type, size, sha256) = file_details(file_reference, original_name);
+            conn.execute(
+                "UPDATE attachments SET file_reference = ?1, original_name = ?2, mime_type = ?3, size = ?4, sha256 = ?5, added_date = ?6,
+                 extracted_text = NULL, text_extracted_at = NULL, extraction_error = NULL WHERE id = ?7",
+                params![file_reference, original_name, mime_type, size, sha256, added_date, id],
+            ).map_err(|e| e.to_string())?;
+        }
+        None => {
+            insert_attachment(conn, record_type, record_id, file_reference, original_name, added_date).map_err(|e| e.to_string())?;
+        }
+    }
+    sync_legacy_column(conn, record_type, record_id)
+}
+
+// Enregistre le fichier joint à la création d'un enregistrement (déjà rangé dans le coffre)
+pub(crate) fn attach_stored_file(conn: &Connection, record_type: &str, record_id: i64, reference: &str, original_path: &str) -> Result<(), String> {
+    let added_date = chrono::Local::now().naive_local().date().to_string();
+    insert_attachment(conn, record_type, record_id, reference, &file_name_of(original_path), &added_date)
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Recopie les pièces jointes dans l'ancienne colonne de l'enregistrement
+pub(crate) fn sync_legacy_column(conn: &Connection, record_type: &str, record_id: i64) -> Result<(), String> {
+    let (table, column, multi) = legacy_column(record_type)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT file_reference, sha256 FROM attachments WHERE record_type = ?1 AND record_id = ?2 ORDER BY position, id"
+    ).map_err(|e| e.to_string())?;
+    let attachment_iter = stmt.query_map(params![record_type, record_id], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+    }).map_err(|e| e.to_string())?;
+    let mut attachments = Vec::new();
+    for attachment in attachment_iter {
+        attachments.push(attachment.map_err(|e| e.to_string())?);
+    }
+
+    let value = if multi {
+        let references: Vec<&str> = attachments.iter().map(|(r, _)| r.as_str()).collect();
+        Some(references.join(";")).filter(|v| !v.is_empty())
+    } else {
+        attachments.first().map(|(r, _)| r.clone())
+    };
+    // admin_doc_path est NOT NULL
+    let value = if table == "admin_documents" { Some(value.unwrap_or_default()) } else { value };
+
+    conn.execute(&format!("UPDATE {} SET {} = ?1 WHERE id = ?2", table, column), params![value, record_id])
+        .map_err(|e| e.to_string())?;
+    if table == "invoices" {
+        conn.execute(
+            "UPDATE invoices SET file_hash = ?1 WHERE id = ?2",
+            params![attachments.first().and_then(|(_, h)| h.clone()), record_id],
+        ).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// Aligne les pièces jointes d'une tâche sur la liste "a;b;c" reçue de l'ancienne interface
+// Les chemins hors coffre y sont copiés : une tâche ne référence jamais un fichier extérieur
+pub(crate) fn replace_task_attachments(conn: &Connection, task_id: i64, value: Option<&str>) -> Result<(), String> {
+    let mut references: Vec<(String, String)> = Vec::new();
+    for path in split_paths(value.unwrap_or_default(), true) {
+        let reference = store_in_vault(path, "tasks", vault_year(None), false)?;
+        if !references.iter().any(|(r, _)| *r == reference) {
+            references.push((reference, file_name_of(path)));
+        }
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT id, file_reference FROM attachments WHERE record_type = 'tasks' AND record_id = ?1"
+    ).map_err(|e| e.to_string())?;
+    let existing: Vec<(i64, String)> = stmt
+        .query_map(params![task_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<_>>()
+        .map_err(|e| e.to_string())?;
+
+    for (id, reference) in &existing {
+        if !references.iter().any(|(r, _)| r == reference) {
+            conn.execute("DELETE FROM attachments WHERE id = ?1", params![id]).map_err(|e| e.to_string())?;
+        }
+    }
+    let added_date = chrono::Local::now().naive_local().date().to_string();
+    for (reference, original_name) in &references {
+        if !existing.iter().any(|(_, r)| r == reference) {
+            insert_attachment(conn, "tasks", task_id, reference, original_name, &added_date)
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    // La colonne reçoit les références du coffre à la place des chemins d'origine
+    sync_legacy_column(conn, "tasks", task_id)
+}
+
+// Supprime les pièces jointes d'un enregistrement supprimé (les fichiers du coffre peuvent être partagés)
+pub(crate) fn delete_record_attachments(conn: &Connection, record_type: &str, record_id: i64) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM attachments WHERE record_type = ?1 AND record_id = ?2",
+        params![record_type, record_id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Reprend dans la table les chemins des anciennes colonnes qui n'y sont pas encore
+pub(crate) fn migrate_legacy_attachments(conn: &Connection) -> rusqlite::Result<()> {
+    let added_date = chrono::Local::now().naive_local().date().to_string();
+    for (table, column, multi, _) in FILE_COLUMNS {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, {} FROM {} WHERE {} IS NOT NULL AND {} != ''",
+            column, table, column, column
+        ))?;
+        let rows: Vec<(i64, String)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        for (record_id, value) in rows {
+            for reference in split_paths(&value, *multi) {
+                let exists: Option<i64> = conn.query_row(
+                    "SELECT id FROM attachments WHERE record_type = ?1 AND record_id = ?2 AND file_reference = ?3",
+                    params![table, record_id, reference],
+                    |row| row.get(0),
+                ).optional()?;
+                if exists.is_none() {
+                    insert_attachment(conn, table, record_id, reference, &file_name_of(reference), &added_date)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn get_attachment(conn: &Connection, id: i64) -> Result<Attachment, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM attachments WHERE id = ?1", ATTACHMENT_COLUMNS),
+        params![id],
+        attachment_from_row,
+    ).map_err(|e| e.to_string())
+}
+
+#[command]
+pub fn add_attachment(record_type: String, record_id: i64, path: String, move_file: Option<bool>) -> Result<Attachment, String> {
+    let (table, _, _) = legacy_column(&record_type)?;
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    check_record_exists(&conn, table, record_id)?;
+
+    let original_name = file_name_of(&path);
+    let reference = store_in_vault(&path, table, vault_year(None), move_file.unwrap_or(false))?;
+    let added_date = chrono::Local::now().naive_local().date().to_string();
+
+    let id = insert_attachment(&conn, table, record_id, &reference, &original_name, &added_date).map_err(|e| e.to_string())?;
+    sync_legacy_column(&conn, table, record_id)?;
+    get_attachment(&conn, id)
+}
+
+#[command]
+pub fn get_attachments(record_type: String, record_id: i64) -> Result<Vec<Attachment>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM attachments WHERE record_type = ?1 AND record_id = ?2 ORDER BY position, id",
+        ATTACHMENT_COLUMNS
+    )).map_err(|e| e.to_string())?;
+    let attachment_iter = stmt.query_map(params![record_type, record_id], attachment_from_row).map_err(|e| e.to_string())?;
+
+    let mut attachments = Vec::new();
+    for attachment in attachment_iter {
+        attachments.push(attachment.map_err(|e| e.to_string())?);
+    }
+    Ok(attachments)
+}
+
+#[command]
+pub fn remove_attachment(id: i64) -> Result<(), String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let attachment = get_attachment(&conn, id)?;
+    conn.execute("DELETE FROM attachments WHERE id = ?1", params![id]).map_err(|e| e.to_string())?;
+    sync_legacy_column(&conn, &attachment.record_type, attachment.record_id)
+}
+
+#[command]
+pub fn reorder_attachments(record_type: String, record_id: i64, attachment_ids: Vec<i64>) -> Result<Vec<Attachment>, String> {
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    for (position, id) in attachment_ids.iter().enumerate() {
+        let updated = tx.execute(
+            "UPDATE attachments SET position = ?1 WHERE id = ?2 AND record_type = ?3 AND record_id = ?4",
+            params![position as i64, id, record_type, record_id],
+        ).map_err(|e| e.to_string())?;
+        if updated == 0 {
+            return Err(format!("Attachment {} does not belong to {} {}", id, record_type, record_id));
+        }
+    }
+    sync_legacy_column(&tx, &record_type, record_id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_attachments(record_type, record_id)
+}
+
+// ---------- Ouverture
+
+// Extensions jamais transmises au programme d'ouverture du système
+const BLOCKED_EXTENSIONS: &[&str] = &[
+    "app", "appimage", "apk", "bat", "bin", "chm", "cmd", "com", "command", "cpl", "deb", "desktop", "dmg", "docm",
+    "dotm", "exe", "hta", "img", "iqy", "iso", "jar", "js", "jse", "lnk", "msc", "msi", "msp", "pif", "pkg", "potm",
+    "ppam", "pptm", "ps1", "py", "reg", "rpm", "run", "scf", "scr", "sh", "url", "vb", "vbe", "vbs", "vhd", "vhdx",
+    "webloc", "workflow", "ws", "wsf", "wsh", "xlam", "xll", "xlsm", "xltm",
+];
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OpenAttachmentError {
+    UnknownAttachment { id: i64 },
+    FileMissing { original_name: String },
+    NotAllowed { reason: String },
+    Failed { message: String },
+}
+
+// Schéma d'URL (http:, mailto:, file:...) ; une lettre seule est un lecteur Windows
+fn has_url_scheme(reference: &str) -> bool {
+    match reference.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.len() > 1
+                && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        }
+        None => false,
+    }
+}
+
+fn is_executable(path: &Path) -> bool {
+    let blocked_extension = path
+        .extension()
+        .map(|e| BLOCKED_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let executable_bit = fs::metadata(path).map(|m| m.permissions().mode() & 0o111 != 0).unwrap_or(false);
+        blocked_extension || executable_bit
+    }
+    #[cfg(not(unix))]
+    {
+        blocked_extension
+    }
+}
+
+// Chemin d'une pièce jointe connue, si la politique autorise son ouverture
+fn openable_path(attachment: &Attachment) -> Result<PathBuf, OpenAttachmentError> {
+    if !is_vault_reference(&attachment.file_reference) && has_url_scheme(&attachment.file_reference) {
+        return Err(OpenAttachmentError::NotAllowed { reason: "URLs cannot be opened".to_string() });
+    }
+    let path = resolve_file_reference(&attachment.file_reference)
+        .map_err(|reason| OpenAttachmentError::NotAllowed { reason })?;
+    if !path.is_file() {
+        return Err(OpenAttachmentError::FileMissing { original_name: attachment.original_name.clone() });
+    }
+    if is_executable(&path) {
+        return Err(OpenAttachmentError::NotAllowed { reason: "Executable files cannot be opened".to_string() });
+    }
+    // Chemin absolu : le programme d'ouverture ne doit pas interpréter la référence lui-même
+    path.canonicalize().map_err(|e| OpenAttachmentError::Failed { message: e.to_string() })
+}
+
+#[command]
+pub fn open_attachment(attachment_id: i64) -> Result<(), OpenAttachmentError> {
+    let conn = Connection::open("app.db").map_err(|e| OpenAttachmentError::Failed { message: e.to_string() })?;
+    let attachment = conn
+        .query_row(
+            &format!("SELECT {} FROM attachments WHERE id = ?1", ATTACHMENT_COLUMNS),
+            params![attachment_id],
+            attachment_from_row,
+        )
+        .optional()
+        .map_err(|e| OpenAttachmentError::Failed { message: e.to_string() })?
+        .ok_or(OpenAttachmentError::UnknownAttachment { id: attachment_id })?;
+    let path = openable_path(&attachment)?;
+
+    #[cfg(target_os = "windows")]
+    let mut opener = Command::new("explorer");
+    #[cfg(target_os = "macos")]
+    let mut opener = Command::new("open");
+    #[cfg(target_os = "linux")]
+    let mut opener = Command::new("xdg-open");
+
+    opener
+        .arg(path)
+        .spawn()
+        .map_err(|e| OpenAttachmentError::Failed { message: e.to_string() })?;
+    Ok(())
+}
@@ -0,0 +1,204 @@
+// ------------------------------------ Task dependencies -----------------------------------------
+//
+// Une tâche peut dépendre d'autres tâches ("payer l'impôt" après "recevoir la taxation") : elle est
+// bloquée tant que l'une d'elles n'est pas terminée. Les liens ne peuvent pas former de cycle.
+
+use std::collections::HashSet;
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::command;
+
+use crate::{apply_task_changes, done_statuses, get_task, Task, TaskChanges};
+
+#[derive(Serialize)]
+pub struct TaskDependencies {
+    // Tâches à terminer avant celle-ci
+    depends_on: Vec<Task>,
+    // Tâches qui attendent celle-ci
+    dependents: Vec<Task>,
+}
+
+#[derive(Serialize)]
+pub struct TaskCompletion {
+    task: Task,
+    unblocked: Vec<Task>,
+}
+
+// Liens (tâche, tâche dont elle dépend) avec le statut de cette dernière
+fn dependency_links(conn: &Connection) -> Result<Vec<(i64, i64, String)>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT d.task_id, d.depends_on_id, p.status FROM task_dependencies d JOIN tasks p ON p.id = d.depends_on_id"
+    ).map_err(|e| e.to_string())?;
+    let links = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<_>>()
+        .map_err(|e| e.to_string())?;
+    Ok(links)
+}
+
+pub(crate) fn mark_blocked(conn: &Connection, tasks: &mut [Task]) -> Result<(), String> {
+    let done = done_statuses(conn)?;
+    let blocked: HashSet<i64> = dependency_links(conn)?
+        .into_iter()
+        .filter(|(_, _, status)| !done.contains(status))
+        .map(|(task_id, _, _)| task_id)
+        .collect();
+    for task in tasks {
+        task.blocked = blocked.contains(&(task.id as i64));
+    }
+    Ok(())
+}
+
+// Vrai si `from` dépend, directement ou non, de `to`
+fn depends_on(conn: &Connection, from: i64, to: i64) -> Result<bool, String> {
+    let mut stmt = conn.prepare(
+        "WITH RECURSIVE prerequisites(id) AS (
+            SELECT depends_on_id FROM task_dependencies WHERE task_id = ?1
+            UNION SELECT d.depends_on_id FROM task_dependencies d JOIN prerequisites p ON d.task_id = p.id
+        ) SELECT 1 FROM prerequisites WHERE id = ?2 LIMIT 1"
+    ).map_err(|e| e.to_string())?;
+    stmt.exists(params![from, to]).map_err(|e| e.to_string())
+}
+
+fn query_tasks(conn: &Connection, ids: &[i64]) -> Result<Vec<Task>, String> {
+    ids.iter().map(|id| get_task(conn, *id as i32)).collect()
+}
+
+#[command]
+pub fn add_task_dependency(task_id: i64, depends_on_id: i64) -> Result<TaskDependencies, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    get_task(&conn, task_id as i32)?;
+    get_task(&conn, depends_on_id as i32)?;
+    if task_id == depends_on_id {
+        return Err("A task cannot depend on itself".to_string());
+    }
+    // Le nouveau lien ferme un cycle si la tâche prérequise attend déjà (même indirectement) la tâche
+    if depends_on(&conn, depends_on_id, task_id)? {
+        return Err(format!("Task {} already depends on task {}: this would create a cycle", depends_on_id, task_id));
+    }
+
+    conn.execute(
+        "INSERT OR IGNORE INTO task_dependencies (task_id, depends_on_id) VALUES (?1, ?2)",
+        params![task_id, depends_on_id],
+    ).map_err(|e| e.to_string())?;
+    get_task_dependencies(task_id)
+}
+
+#[command]
+pub fn remove_task_dependency(task_id: i64, depends_on_id: i64) -> Result<TaskDependencies, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM task_dependencies WHERE task_id = ?1 AND depends_on_id = ?2",
+        params![task_id, depends_on_id],
+    ).map_err(|e| e.to_string())?;
+    get_task_dependencies(task_id)
+}
+
+#[command]
+pub fn get_task_dependencies(task_id: i64) -> Result<TaskDependencies, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    get_task(&conn, task_id as i32)?;
+    let links = dependency_links(&conn)?;
+
+    let prerequisites: Vec<i64> = links.iter().filter(|(t, _, _)| *t == task_id).map(|(_, d, _)| *d).collect();
+    let dependents: Vec<i64> = links.iter().filter(|(_, d, _)| *d == task_id).map(|(t, _, _)| *t).collect();
+    Ok(TaskDependencies {
+        depends_on: query_tasks(&conn, &prerequisites)?,
+        dependents: query_tasks(&conn, &dependents)?,
+    })
+}
+
+// Premier statut coché "Done" dans les réglages (Completed, Terminée, Erledigt...)
+fn done_status(conn: &Connection) -> Result<String, String> {
+    let done = done_statuses(conn)?;
+    let mut stmt = conn.prepare("SELECT name FROM task_statuses ORDER BY id").map_err(|e| e.to_string())?;
+    let statuses: Vec<String> = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<_>>()
+        .map_err(|e| e.to_string())?;
+    statuses
+        .into_iter()
+        .find(|status| done.contains(status))
+        .ok_or_else(|| "No task status is marked as done in the settings".to_string())
+}
+
+// Termine la tâche et renvoie les tâches qui ne sont plus bloquées grâce à elle
+#[command]
+pub fn complete_task(id: i64) -> Result<TaskCompletion, String> {
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let task = get_task(&tx, id as i32)?;
+    let dependents: Vec<i64> = dependency_links(&tx)?
+        .into_iter()
+        .filter(|(_, depends_on_id, _)| *depends_on_id == id)
+        .map(|(task_id, _, _)| task_id)
+        .collect();
+    let blocked_before: HashSet<i64> = query_tasks(&tx, &dependents)?
+        .into_iter()
+        .filter(|t| t.blocked)
+        .map(|t| t.id as i64)
+        .collect();
+
+    let done = done_statuses(&tx)?;
+    let task = if done.contains(&task.status) {
+        task
+    } else {
+        apply_task_changes(&tx, id as i32, TaskChanges { status: Some(done_status(&tx)?), ..Default::default() })?
+    };
+
+    // Tâches encore ouvertes qui étaient bloquées et ne le sont plus
+    let unblocked = query_tasks(&tx, &dependents)?
+        .into_iter()
+        .filter(|t| blocked_before.contains(&(t.id as i64)) && !t.blocked && !done.contains(&t.status))
+        .collect();
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(TaskCompletion { task, unblocked })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(pairs: &[(i64, i64)]) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE task_dependencies (id INTEGER PRIMARY KEY, task_id INTEGER NOT NULL, depends_on_id INTEGER NOT NULL)",
+            [],
+        ).unwrap();
+        for (task_id, depends_on_id) in pairs {
+            conn.execute(
+                "INSERT INTO task_dependencies (task_id, depends_on_id) VALUES (?1, ?2)",
+                params![task_id, depends_on_id],
+            ).unwrap();
+        }
+        conn
+    }
+
+    #[test]
+    fn indirect_dependencies_are_found() {
+        // 1 attend 2, qui attend 3 ; 4 attend 3
+        let conn = links(&[(1, 2), (2, 3), (4, 3)]);
+        assert!(depends_on(&conn, 1, 2).unwrap());
+        assert!(depends_on(&conn, 1, 3).unwrap());
+        assert!(!depends_on(&conn, 3, 1).unwrap());
+        assert!(!depends_on(&conn, 1, 4).unwrap());
+    }
+
+    #[test]
+    fn closing_link_is_detected_as_a_cycle() {
+        // Ajouter "3 attend 1" fermerait la boucle 1 -> 2 -> 3 -> 1
+        let conn = links(&[(1, 2), (2, 3)]);
+        assert!(depends_on(&conn, 1, 3).unwrap());
+        assert!(!depends_on(&conn, 2, 1).unwrap());
+    }
+
+    #[test]
+    fn existing_cycle_does_not_loop_forever() {
+        let conn = links(&[(1, 2), (2, 1)]);
+        assert!(depends_on(&conn, 1, 1).unwrap());
+        assert!(!depends_on(&conn, 1, 3).unwrap());
+    }
+}
@@ -0,0 +1,152 @@
+// ------------------------------------ Drafts inbox -----------------------------------------
+//
+// Les enregistrements créés automatiquement (dossier surveillé, e-mails, modèles récurrents) sont
+// des brouillons : ils n'apparaissent ni dans les listes ni dans les totaux tant qu'ils n'ont pas
+// été relus. L'inbox les regroupe ; on les approuve, ou on les rejette (ils sont alors supprimés).
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::json;
+use tauri::command;
+
+use crate::attachments::delete_record_attachments;
+use crate::retention::{record_files, remove_unreferenced_files};
+use crate::versions::delete_document_versions;
+use crate::{find_duplicate_invoices, query_admin_docs, query_invoices, query_revenues, AdminDocument, DuplicateCandidate, Invoice, Revenue};
+
+struct DraftTable {
+    table: &'static str,
+    // Condition que l'enregistrement doit remplir pour être approuvé, et sa description pour l'erreur
+    complete: &'static str,
+    requirement: &'static str,
+}
+
+const DRAFT_TABLES: &[DraftTable] = &[
+    DraftTable {
+        table: "invoices",
+        complete: "creditor != '' AND amount > 0 AND TRIM(concern) != '' AND TRIM(category) != ''",
+        requirement: "a creditor, a concern, a category and a positive amount",
+    },
+    DraftTable {
+        table: "revenues",
+        complete: "source != '' AND revenue_amount > 0 AND TRIM(COALESCE(revenue_type, '')) != ''",
+        requirement: "a source, a type and a positive amount",
+    },
+    DraftTable {
+        table: "admin_documents",
+        complete: "admin_doc_description != '' AND TRIM(admin_doc_concern) != '' AND TRIM(admin_doc_category) != ''",
+        requirement: "a description, a concern and a category",
+    },
+];
+
+// Facture en brouillon et les factures existantes qui lui ressemblent
+#[derive(Serialize)]
+pub struct DraftInvoice {
+    #[serde(flatten)]
+    invoice: Invoice,
+    duplicates: Vec<DuplicateCandidate>,
+}
+
+#[derive(Serialize)]
+pub struct Inbox {
+    invoices: Vec<DraftInvoice>,
+    revenues: Vec<Revenue>,
+    admin_documents: Vec<AdminDocument>,
+    total: usize,
+}
+
+#[derive(Serialize)]
+pub struct RejectSummary {
+    deleted_files: usize,
+    failed_files: Vec<String>,
+}
+
+fn draft_table(record_type: &str) -> Result<&'static DraftTable, String> {
+    DRAFT_TABLES
+        .iter()
+        .find(|t| t.table == record_type)
+        .ok_or_else(|| format!("Unsupported record type '{}'", record_type))
+}
+
+// Vérifie que l'enregistrement existe et est bien un brouillon
+fn check_draft(conn: &Connection, table: &DraftTable, record_id: i64) -> Result<(), String> {
+    let is_draft: bool = conn
+        .query_row(&format!("SELECT is_draft FROM {} WHERE id = ?1", table.table), params![record_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("{} {} not found", table.table, record_id))?;
+    if is_draft {
+        Ok(())
+    } else {
+        Err(format!("{} {} is not a draft", table.table, record_id))
+    }
+}
+
+fn draft_invoice(conn: &Connection, invoice: Invoice) -> Result<DraftInvoice, String> {
+    let file_hash: Option<String> = conn
+        .query_row("SELECT file_hash FROM invoices WHERE id = ?1", params![invoice.id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    let duplicates = find_duplicate_invoices(
+        conn, &invoice.creditor, invoice.amount, &invoice.due_date, invoice.reference.as_deref(), file_hash.as_deref()
+    )?
+    .into_iter()
+    .filter(|candidate| candidate.invoice.id != invoice.id)
+    .collect();
+    Ok(DraftInvoice { invoice, duplicates })
+}
+
+// Brouillons de tous les types, les plus anciens en premier
+#[command]
+pub fn get_inbox() -> Result<Inbox, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let invoices = query_invoices(&conn, "WHERE is_draft = 1 ORDER BY id")?
+        .into_iter()
+        .map(|invoice| draft_invoice(&conn, invoice))
+        .collect::<Result<Vec<_>, String>>()?;
+    let revenues = query_revenues(&conn, "WHERE is_draft = 1 ORDER BY id")?;
+    let admin_documents = query_admin_docs(&conn, "WHERE is_draft = 1", &[])?;
+    let total = invoices.len() + revenues.len() + admin_documents.len();
+    Ok(Inbox { invoices, revenues, admin_documents, total })
+}
+
+// L'enregistrement approuvé compte désormais comme les autres
+#[command]
+pub fn approve_draft(record_type: String, record_id: i64) -> Result<(), String> {
+    let table = draft_table(&record_type)?;
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    check_draft(&conn, table, record_id)?;
+
+    let updated = conn.execute(
+        &format!("UPDATE {} SET is_draft = 0 WHERE id = ?1 AND {}", table.table, table.complete),
+        params![record_id],
+    ).map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("{} {} needs {} before it can be approved", table.table, record_id, table.requirement));
+    }
+    Ok(())
+}
+
+// Supprime le brouillon et ses fichiers ; le rejet est consigné dans le journal d'audit
+#[command]
+pub fn reject_draft(record_type: String, record_id: i64) -> Result<RejectSummary, String> {
+    let table = draft_table(&record_type)?;
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    check_draft(&conn, table, record_id)?;
+    let files = record_files(&conn, table.table, record_id)?;
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(&format!("DELETE FROM {} WHERE id = ?1", table.table), params![record_id])
+        .map_err(|e| e.to_string())?;
+    delete_record_attachments(&tx, table.table, record_id)?;
+    if table.table == "admin_documents" {
+        delete_document_versions(&tx, record_id)?;
+    }
+    tx.execute(
+        "INSERT INTO audit_log (timestamp, action, record_type, record_id, details) VALUES (?1, 'draft_rejected', ?2, ?3, ?4)",
+        params![chrono::Local::now().naive_local().to_string(), table.table, record_id, json!({ "files": files }).to_string()],
+    ).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    let (deleted_files, failed_files) = remove_unreferenced_files(&conn, files)?;
+    Ok(RejectSummary { deleted_files, failed_files })
+}
@@ -0,0 +1,93 @@
+// ------------------------------------ Document expiry -----------------------------------------
+//
+// Passeports, cartes d'identité, attestations d'assurance, contrats : un document avec une date
+// d'expiration génère une tâche de renouvellement quand on entre dans son délai de renouvellement.
+
+use chrono::{Duration, NaiveDate};
+use rusqlite::{params, Connection};
+use tauri::command;
+
+use crate::{query_admin_docs, AdminDocument};
+
+// Délai utilisé quand le document n'en précise pas
+const DEFAULT_RENEWAL_LEAD_DAYS: i64 = 30;
+// Dix ans : au-delà, le calcul de la date de renouvellement sortirait des dates représentables
+pub(crate) const MAX_RENEWAL_LEAD_DAYS: i64 = 3_650;
+const RENEWAL_TASK_STATUS: &str = "Pending";
+const RENEWAL_TASK_PRIORITY: &str = "High";
+
+fn today() -> NaiveDate {
+    chrono::Local::now().naive_local().date()
+}
+
+// Crée les tâches de renouvellement des documents entrés dans leur délai, une seule fois par échéance
+pub(crate) fn create_renewal_tasks() -> Result<usize, String> {
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let documents = query_admin_docs(
+        &tx,
+        // Un brouillon n'a sa tâche qu'une fois approuvé
+        "WHERE expiry_date IS NOT NULL AND renewal_task_id IS NULL AND lifecycle_status != 'archived' AND is_draft = 0",
+        &[],
+    )?;
+    let today = today();
+    let current_date = chrono::Local::now().naive_local().to_string();
+
+    let mut created = 0;
+    for document in documents {
+        let expiry = match document.expiry_date.as_deref().and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok()) {
+            Some(expiry) => expiry,
+            None => continue,
+        };
+        let lead_days = document.renewal_lead_days.unwrap_or(DEFAULT_RENEWAL_LEAD_DAYS);
+        // Un délai trop long pour être calculé (base modifiée à la main) veut dire "à renouveler dès maintenant"
+        let renew_from = Duration::try_days(lead_days).and_then(|lead| expiry.checked_sub_signed(lead));
+        if renew_from.is_some_and(|renew_from| today < renew_from) {
+            continue;
+        }
+
+        tx.execute(
+            "INSERT INTO tasks (title, description, status, priority, due_date, creation_date, category, attachments, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, '', NULL, ?6)",
+            params![
+                format!("Renew {}", document.admin_doc_description),
+                format!("{} / {} expires on {}", document.admin_doc_concern, document.admin_doc_category, expiry),
+                RENEWAL_TASK_STATUS,
+                RENEWAL_TASK_PRIORITY,
+                expiry.to_string(),
+                current_date
+            ],
+        ).map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE admin_documents SET renewal_task_id = ?1 WHERE id = ?2",
+            params![tx.last_insert_rowid(), document.id],
+        ).map_err(|e| e.to_string())?;
+        created += 1;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(created)
+}
+
+// Documents qui expirent dans les `days` prochains jours (ou qui ont déjà expiré), par échéance
+#[command]
+pub fn get_expiring_admin_docs(days: i64, include_drafts: Option<bool>) -> Result<Vec<AdminDocument>, String> {
+    if days < 0 {
+        return Err("The number of days cannot be negative".to_string());
+    }
+    create_renewal_tasks()?;
+
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let limit = Duration::try_days(days)
+        .and_then(|days| today().checked_add_signed(days))
+        .ok_or_else(|| format!("The number of days is too large: {}", days))?
+        .to_string();
+    let filter = if include_drafts.unwrap_or(false) {
+        "WHERE expiry_date IS NOT NULL AND expiry_date <= ?1 AND lifecycle_status != 'archived'"
+    } else {
+        "WHERE expiry_date IS NOT NULL AND expiry_date <= ?1 AND lifecycle_status != 'archived' AND is_draft = 0"
+    };
+    let mut documents = query_admin_docs(&conn, filter, &[&limit])?;
+    documents.sort_by(|a, b| a.expiry_date.cmp(&b.expiry_date));
+    Ok(documents)
+}
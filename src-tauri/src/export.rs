@@ -0,0 +1,532 @@
+// ------------------------------------ Exports (CSV / XLSX / ODS) -----------------------------------------
+//
+// Les exports sont faits côté Rust directement depuis la base, ligne par ligne, pour ne pas dépendre
+// de ce que la vue a chargé et pour tenir sur de gros volumes.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use chrono::NaiveDate;
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection};
+use rust_xlsxwriter::{Format, Workbook};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+#[derive(Deserialize)]
+pub struct ExportOptions {
+    // "invoices", "revenues", "admin_documents" ou "tasks"
+    record_type: String,
+    // "csv", "xlsx" ou "ods"
+    format: String,
+    // Colonnes à exporter, dans l'ordre ; toutes par défaut
+    columns: Option<Vec<String>>,
+    // Bornes incluses sur la date principale (échéance, réception...), au format YYYY-MM-DD
+    date_from: Option<String>,
+    date_to: Option<String>,
+    status: Option<String>,
+    category: Option<String>,
+    // "fr-CH", "de-CH", "fr-FR", "de-DE", "en-US" ; ISO par défaut
+    locale: Option<String>,
+    include_drafts: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct ExportSummary {
+    path: String,
+    rows: usize,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ColumnKind {
+    Integer,
+    Number,
+    Date,
+    Text,
+}
+
+struct ExportTable {
+    table: &'static str,
+    columns: &'static [(&'static str, ColumnKind)],
+    date_column: Option<&'static str>,
+    status_column: Option<&'static str>,
+    category_column: &'static str,
+    // Les brouillons (enregistrements créés automatiquement, pas encore relus) sont exclus par défaut
+    has_drafts: bool,
+}
+
+const INVOICES: ExportTable = ExportTable {
+    table: "invoices",
+    columns: &[
+        ("id", ColumnKind::Integer),
+        ("creditor", ColumnKind::Text),
+        ("concern", ColumnKind::Text),
+        ("category", ColumnKind::Text),
+        ("amount", ColumnKind::Number),
+        ("due_date", ColumnKind::Date),
+        ("description", ColumnKind::Text),
+        ("status", ColumnKind::Text),
+        ("payment_date", ColumnKind::Date),
+        ("reference", ColumnKind::Text),
+        ("path", ColumnKind::Text),
+    ],
+    date_column: Some("due_date"),
+    status_column: Some("status"),
+    category_column: "category",
+    has_drafts: true,
+};
+
+const REVENUES: ExportTable = ExportTable {
+    table: "revenues",
+    columns: &[
+        ("id", ColumnKind::Integer),
+        ("source", ColumnKind::Text),
+        ("revenue_type", ColumnKind::Text),
+        ("revenue_amount", ColumnKind::Number),
+        ("receipt_date", ColumnKind::Date),
+        ("revenue_description", ColumnKind::Text),
+        ("revenue_path", ColumnKind::Text),
+    ],
+    date_column: Some("receipt_date"),
+    status_column: None,
+    category_column: "revenue_type",
+    has_drafts: true,
+};
+
+const ADMIN_DOCUMENTS: ExportTable = ExportTable {
+    table: "admin_documents",
+    columns: &[
+        ("id", ColumnKind::Integer),
+        ("admin_doc_concern", ColumnKind::Text),
+        ("admin_doc_category", ColumnKind::Text),
+        ("admin_doc_description", ColumnKind::Text),
+        ("admin_doc_status", ColumnKind::Text),
+        ("issue_date", ColumnKind::Date),
+        ("expiry_date", ColumnKind::Date),
+        ("admin_doc_path", ColumnKind::Text),
+        ("lifecycle_status", ColumnKind::Text),
+    ],
+    date_column: None,
+    status_column: Some("admin_doc_status"),
+    category_column: "admin_doc_category",
+    has_drafts: true,
+};
+
+const TASKS: ExportTable = ExportTable {
+    table: "tasks",
+    columns: &[
+        ("id", ColumnKind::Integer),
+        ("title", ColumnKind::Text),
+        ("description", ColumnKind::Text),
+        ("status", ColumnKind::Text),
+        ("priority", ColumnKind::Text),
+        ("due_date", ColumnKind::Date),
+        ("creation_date", ColumnKind::Text),
+        ("updated_at", ColumnKind::Text),
+        ("completed_at", ColumnKind::Text),
+        ("recurrence", ColumnKind::Text),
+        ("category", ColumnKind::Text),
+        ("attachments", ColumnKind::Text),
+    ],
+    date_column: Some("due_date"),
+    status_column: Some("status"),
+    category_column: "category",
+    has_drafts: false,
+};
+
+fn export_table(record_type: &str) -> Result<&'static ExportTable, String> {
+    match record_type {
+        "invoices" => Ok(&INVOICES),
+        "revenues" => Ok(&REVENUES),
+        "admin_documents" => Ok(&ADMIN_DOCUMENTS),
+        "tasks" => Ok(&TASKS),
+        other => Err(format!("Unsupported record type: {}", other)),
+    }
+}
+
+// ---------- Locale
+
+struct LocaleFormat {
+    decimal_separator: char,
+    thousands_separator: Option<char>,
+    // Format chrono pour le CSV et le texte affiché dans l'ODS
+    date_format: &'static str,
+    // Format de cellule Excel équivalent
+    excel_date_format: &'static str,
+    csv_delimiter: u8,
+}
+
+fn locale_format(locale: Option<&str>) -> LocaleFormat {
+    match locale.unwrap_or("") {
+        "fr-CH" | "de-CH" | "it-CH" => LocaleFormat {
+            decimal_separator: '.',
+            thousands_separator: Some('\''),
+            date_format: "%d.%m.%Y",
+            excel_date_format: "dd.mm.yyyy",
+            csv_delimiter: b';',
+        },
+        "fr-FR" => LocaleFormat {
+            decimal_separator: ',',
+            thousands_separator: Some(' '),
+            date_format: "%d/%m/%Y",
+            excel_date_format: "dd/mm/yyyy",
+            csv_delimiter: b';',
+        },
+        "de-DE" => LocaleFormat {
+            decimal_separator: ',',
+            thousands_separator: Some('.'),
+            date_format: "%d.%m.%Y",
+            excel_date_format: "dd.mm.yyyy",
+            csv_delimiter: b';',
+        },
+        "en-US" => LocaleFormat {
+            decimal_separator: '.',
+            thousands_separator: Some(','),
+            date_format: "%m/%d/%Y",
+            excel_date_format: "mm/dd/yyyy",
+            csv_delimiter: b',',
+        },
+        _ => LocaleFormat {
+            decimal_separator: '.',
+            thousands_separator: None,
+            date_format: "%Y-%m-%d",
+            excel_date_format: "yyyy-mm-dd",
+            csv_delimiter: b',',
+        },
+    }
+}
+
+fn format_number(value: f64, locale: &LocaleFormat) -> String {
+    let formatted = format!("{:.2}", value.abs());
+    let (integer, decimals) = formatted.split_once('.').unwrap_or((&formatted, "00"));
+
+    let mut grouped = String::new();
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            if let Some(separator) = locale.thousands_separator {
+                grouped.push(separator);
+            }
+        }
+        grouped.push(digit);
+    }
+
+    let sign = if value < 0.0 { "-" } else { "" };
+    format!("{}{}{}{}", sign, grouped, locale.decimal_separator, decimals)
+}
+
+// ---------- Cellules et writers
+
+enum Cell {
+    Empty,
+    Integer(i64),
+    Number(f64),
+    Date(NaiveDate),
+    Text(String),
+}
+
+fn to_cell(value: Value, kind: ColumnKind) -> Cell {
+    match (value, kind) {
+        (Value::Null, _) => Cell::Empty,
+        (Value::Integer(i), ColumnKind::Number) => Cell::Number(i as f64),
+        (Value::Integer(i), _) => Cell::Integer(i),
+        (Value::Real(r), _) => Cell::Number(r),
+        (Value::Text(t), ColumnKind::Date) => match NaiveDate::parse_from_str(&t, "%Y-%m-%d") {
+            Ok(date) => Cell::Date(date),
+            Err(_) if t.is_empty() => Cell::Empty,
+            Err(_) => Cell::Text(t),
+        },
+        (Value::Text(t), _) => Cell::Text(t),
+        (Value::Blob(_), _) => Cell::Empty,
+    }
+}
+
+fn cell_text(cell: &Cell, locale: &LocaleFormat) -> String {
+    match cell {
+        Cell::Empty => String::new(),
+        Cell::Integer(i) => i.to_string(),
+        Cell::Number(n) => format_number(*n, locale),
+        Cell::Date(d) => d.format(locale.date_format).to_string(),
+        Cell::Text(t) => t.clone(),
+    }
+}
+
+trait SheetWriter {
+    fn write_row(&mut self, cells: &[Cell]) -> Result<(), String>;
+    fn finish(self: Box<Self>) -> Result<(), String>;
+}
+
+struct CsvSheet {
+    writer: csv::Writer<File>,
+    locale: LocaleFormat,
+}
+
+impl SheetWriter for CsvSheet {
+    fn write_row(&mut self, cells: &[Cell]) -> Result<(), String> {
+        let record: Vec<String> = cells.iter().map(|c| cell_text(c, &self.locale)).collect();
+        self.writer.write_record(&record).map_err(|e| e.to_string())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), String> {
+        self.writer.flush().map_err(|e| e.to_string())
+    }
+}
+
+struct XlsxSheet {
+    workbook: Workbook,
+    path: String,
+    row: u32,
+    header_format: Format,
+    number_format: Format,
+    date_format: Format,
+}
+
+impl SheetWriter for XlsxSheet {
+    fn write_row(&mut self, cells: &[Cell]) -> Result<(), String> {
+        let worksheet = self.workbook.worksheet_from_index(0).map_err(|e| e.to_string())?;
+        for (col, cell) in cells.iter().enumerate() {
+            let col = col as u16;
+            let result = match cell {
+                Cell::Empty => continue,
+                Cell::Text(t) if self.row == 0 => worksheet.write_string_with_format(self.row, col, t, &self.header_format),
+                Cell::Text(t) => worksheet.write_string(self.row, col, t),
+                Cell::Integer(i) => worksheet.write_number(self.row, col, *i as f64),
+                Cell::Number(n) => worksheet.write_number_with_format(self.row, col, *n, &self.number_format),
+                Cell::Date(d) => worksheet.write_datetime_with_format(self.row, col, d, &self.date_format),
+            };
+            result.map_err(|e| e.to_string())?;
+        }
+        self.row += 1;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), String> {
+        self.workbook.save(&self.path).map_err(|e| e.to_string())
+    }
+}
+
+const ODS_MIMETYPE: &str = "application/vnd.oasis.opendocument.spreadsheet";
+
+struct OdsSheet {
+    zip: ZipWriter<BufWriter<File>>,
+    locale: LocaleFormat,
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Traduit le format chrono de la locale en style de date ODF
+fn ods_date_style(date_format: &str) -> String {
+    let mut style = String::new();
+    let mut chars = date_format.chars();
+    while let Some(c) = chars.next() {
+        if c == '%' {
+            match chars.next() {
+                Some('d') => style.push_str(r#"<number:day number:style="long"/>"#),
+                Some('m') => style.push_str(r#"<number:month number:style="long"/>"#),
+                Some('Y') => style.push_str(r#"<number:year number:style="long"/>"#),
+                _ => {}
+            }
+        } else {
+            style.push_str(&format!("<number:text>{}</number:text>", xml_escape(&c.to_string())));
+        }
+    }
+    style
+}
+
+impl OdsSheet {
+    fn create(path: &str, table_name: &str, locale: LocaleFormat) -> Result<OdsSheet, String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let mut zip = ZipWriter::new(BufWriter::new(file));
+
+        // Le type MIME doit être la première entrée, non compressée
+        zip.start_file("mimetype", SimpleFileOptions::default().compression_method(CompressionMethod::Stored))
+            .map_err(|e| e.to_string())?;
+        zip.write_all(ODS_MIMETYPE.as_bytes()).map_err(|e| e.to_string())?;
+
+        zip.start_file("META-INF/manifest.xml", SimpleFileOptions::default()).map_err(|e| e.to_string())?;
+        write!(
+            zip,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0" manifest:version="1.2">
+ <manifest:file-entry manifest:full-path="/" manifest:media-type="{}"/>
+ <manifest:file-entry manifest:full-path="content.xml" manifest:media-type="text/xml"/>
+</manifest:manifest>"#,
+            ODS_MIMETYPE
+        ).map_err(|e| e.to_string())?;
+
+        zip.start_file("content.xml", SimpleFileOptions::default()).map_err(|e| e.to_string())?;
+        write!(
+            zip,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0" xmlns:number="urn:oasis:names:tc:opendocument:xmlns:datastyle:1.0" office:version="1.2">
+<office:automatic-styles>
+<number:date-style style:name="N1">{}</number:date-style>
+<number:number-style style:name="N2"><number:number number:decimal-places="2" number:min-integer-digits="1" number:grouping="true"/></number:number-style>
+<style:style style:name="ce1" style:family="table-cell" style:data-style-name="N1"/>
+<style:style style:name="ce2" style:family="table-cell" style:data-style-name="N2"/>
+</office:automatic-styles>
+<office:body><office:spreadsheet><table:table table:name="{}">
+"#,
+            ods_date_style(locale.date_format),
+            xml_escape(table_name)
+        ).map_err(|e| e.to_string())?;
+
+        Ok(OdsSheet { zip, locale })
+    }
+}
+
+impl SheetWriter for OdsSheet {
+    fn write_row(&mut self, cells: &[Cell]) -> Result<(), String> {
+        let mut xml = String::from("<table:table-row>");
+        for cell in cells {
+            let text = xml_escape(&cell_text(cell, &self.locale));
+            match cell {
+                Cell::Empty => xml.push_str("<table:table-cell/>"),
+                Cell::Integer(i) => xml.push_str(&format!(
+                    r#"<table:table-cell office:value-type="float" office:value="{}"><text:p>{}</text:p></table:table-cell>"#,
+                    i, text
+                )),
+                Cell::Number(n) => xml.push_str(&format!(
+                    r#"<table:table-cell table:style-name="ce2" office:value-type="float" office:value="{}"><text:p>{}</text:p></table:table-cell>"#,
+                    n, text
+                )),
+                Cell::Date(d) => xml.push_str(&format!(
+                    r#"<table:table-cell table:style-name="ce1" office:value-type="date" office:date-value="{}"><text:p>{}</text:p></table:table-cell>"#,
+                    d.format("%Y-%m-%d"), text
+                )),
+                Cell::Text(_) => xml.push_str(&format!(
+                    r#"<table:table-cell office:value-type="string"><text:p>{}</text:p></table:table-cell>"#,
+                    text
+                )),
+            }
+        }
+        xml.push_str("</table:table-row>\n");
+        self.zip.write_all(xml.as_bytes()).map_err(|e| e.to_string())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), String> {
+        self.zip
+            .write_all(b"</table:table></office:spreadsheet></office:body></office:document-content>")
+            .map_err(|e| e.to_string())?;
+        let mut writer = self.zip.finish().map_err(|e| e.to_string())?;
+        writer.flush().map_err(|e| e.to_string())
+    }
+}
+
+fn open_writer(format: &str, path: &str, table_name: &str, locale: LocaleFormat) -> Result<Box<dyn SheetWriter>, String> {
+    match format {
+        "csv" => {
+            let writer = csv::WriterBuilder::new()
+                .delimiter(locale.csv_delimiter)
+                .from_path(path)
+                .map_err(|e| e.to_string())?;
+            Ok(Box::new(CsvSheet { writer, locale }))
+        }
+        "xlsx" => {
+            let mut workbook = Workbook::new();
+            workbook.add_worksheet_with_constant_memory().set_name(table_name).map_err(|e| e.to_string())?;
+            Ok(Box::new(XlsxSheet {
+                workbook,
+                path: path.to_string(),
+                row: 0,
+                header_format: Format::new().set_bold(),
+                number_format: Format::new().set_num_format("#,##0.00"),
+                date_format: Format::new().set_num_format(locale.excel_date_format),
+            }))
+        }
+        "ods" => Ok(Box::new(OdsSheet::create(path, table_name, locale)?)),
+        other => Err(format!("Unsupported export format: {}", other)),
+    }
+}
+
+#[command]
+pub fn export_records(path: String, options: ExportOptions) -> Result<ExportSummary, String> {
+    let table = export_table(&options.record_type)?;
+
+    let columns: Vec<(&str, ColumnKind)> = match &options.columns {
+        Some(selected) => selected
+            .iter()
+            .map(|name| {
+                table.columns
+                    .iter()
+                    .find(|(column, _)| column == name)
+                    .copied()
+                    .ok_or_else(|| format!("Unknown column '{}' for {}", name, table.table))
+            })
+            .collect::<Result<_, _>>()?,
+        None => table.columns.to_vec(),
+    };
+    if columns.is_empty() {
+        return Err("No column selected".to_string());
+    }
+
+    // Filtres
+    let mut conditions = Vec::new();
+    let mut values: Vec<String> = Vec::new();
+    if options.date_from.is_some() || options.date_to.is_some() {
+        let date_column = table.date_column
+            .ok_or_else(|| format!("Date filters are not supported for {}", table.table))?;
+        if let Some(from) = &options.date_from {
+            values.push(from.clone());
+            conditions.push(format!("{} >= ?{}", date_column, values.len()));
+        }
+        if let Some(to) = &options.date_to {
+            values.push(to.clone());
+            conditions.push(format!("{} <= ?{}", date_column, values.len()));
+        }
+    }
+    if let Some(status) = &options.status {
+        let status_column = table.status_column
+            .ok_or_else(|| format!("Status filter is not supported for {}", table.table))?;
+        values.push(status.clone());
+        conditions.push(format!("{} = ?{}", status_column, values.len()));
+    }
+    if let Some(category) = &options.category {
+        values.push(category.clone());
+        conditions.push(format!("{} = ?{}", table.category_column, values.len()));
+    }
+
+    if table.has_drafts && !options.include_drafts.unwrap_or(false) {
+        conditions.push("is_draft = 0".to_string());
+    }
+
+    let mut sql = format!(
+        "SELECT {} FROM {}",
+        columns.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", "),
+        table.table
+    );
+    if !conditions.is_empty() {
+        sql.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+    }
+    sql.push_str(&format!(" ORDER BY {}, id", table.date_column.unwrap_or("id")));
+
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let mut rows = stmt.query(params_from_iter(values.iter())).map_err(|e| e.to_string())?;
+
+    let locale = locale_format(options.locale.as_deref());
+    let mut writer = open_writer(&options.format, &path, table.table, locale)?;
+
+    let header: Vec<Cell> = columns.iter().map(|(name, _)| Cell::Text(name.to_string())).collect();
+    writer.write_row(&header)?;
+
+    let mut count = 0;
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        let mut cells = Vec::with_capacity(columns.len());
+        for (i, (_, kind)) in columns.iter().enumerate() {
+            let value: Value = row.get(i).map_err(|e| e.to_string())?;
+            cells.push(to_cell(value, *kind));
+        }
+        writer.write_row(&cells)?;
+        count += 1;
+    }
+    writer.finish()?;
+
+    Ok(ExportSummary { path, rows: count })
+}
@@ -0,0 +1,158 @@
+// ------------------------------------ Text extraction -----------------------------------------
+//
+// Extrait en tâche de fond le texte des pièces jointes PDF et texte des factures, revenus et
+// documents administratifs. Le texte est stocké dans la table des pièces jointes, d'où les
+// triggers de l'index de recherche le reprennent. L'avancement est envoyé à l'interface par des
+// événements Tauri.
+
+use std::fs;
+use std::panic;
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use log::error;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::{command, AppHandle, Manager, State};
+
+use crate::vault::resolve_file_reference;
+
+// Les nouvelles pièces jointes sont reprises au plus tard après ce délai
+const RESCAN_INTERVAL: Duration = Duration::from_secs(60);
+// Au-delà, le texte est tronqué : seul le début d'un document sert à le retrouver
+const MAX_TEXT_BYTES: usize = 512 * 1024;
+const PROGRESS_EVENT: &str = "text-extraction-progress";
+const FINISHED_EVENT: &str = "text-extraction-finished";
+
+const PENDING_FILTER: &str = "text_extracted_at IS NULL
+    AND record_type IN ('invoices', 'revenues', 'admin_documents')
+    AND (mime_type = 'application/pdf' OR mime_type LIKE 'text/%')";
+
+pub struct TextExtractor {
+    wake: Mutex<Sender<()>>,
+}
+
+#[derive(Serialize, Clone)]
+struct ExtractionProgress {
+    attachment_id: i64,
+    original_name: String,
+    processed: usize,
+    total: usize,
+    error: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+struct ExtractionFinished {
+    extracted: usize,
+    failed: usize,
+}
+
+fn normalize(text: &str) -> String {
+    let mut text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.len() > MAX_TEXT_BYTES {
+        let mut end = MAX_TEXT_BYTES;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+    text
+}
+
+pub(crate) fn extract_text(path: &Path, mime_type: &str) -> Result<String, String> {
+    if !path.is_file() {
+        return Err("File not found".to_string());
+    }
+    let text = if mime_type == "application/pdf" {
+        // Le lecteur PDF peut paniquer sur un fichier mal formé : cela ne doit pas arrêter l'extracteur
+        let path = path.to_path_buf();
+        panic::catch_unwind(move || pdf_extract::extract_text(path))
+            .map_err(|_| "Unreadable PDF".to_string())?
+            .map_err(|e| e.to_string())?
+    } else {
+        String::from_utf8_lossy(&fs::read(path).map_err(|e| e.to_string())?).to_string()
+    };
+    Ok(normalize(&text))
+}
+
+fn extract_pending(app: &AppHandle) -> Result<(), String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, file_reference, original_name, mime_type FROM attachments WHERE {} ORDER BY id",
+        PENDING_FILTER
+    )).map_err(|e| e.to_string())?;
+    let pending: Vec<(i64, String, String, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<_>>()
+        .map_err(|e| e.to_string())?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let total = pending.len();
+    let mut failed = 0;
+    for (processed, (id, reference, original_name, mime_type)) in pending.into_iter().enumerate() {
+        let result = resolve_file_reference(&reference).and_then(|path| extract_text(&path, &mime_type));
+        let current_date = chrono::Local::now().naive_local().to_string();
+        // Un échec est aussi daté, pour ne pas réessayer sans fin ; un relink remet la pièce jointe en attente
+        let (text, extraction_error) = match result {
+            Ok(text) => (Some(text), None),
+            Err(e) => {
+                failed += 1;
+                (None, Some(e))
+            }
+        };
+        conn.execute(
+            "UPDATE attachments SET extracted_text = ?1, text_extracted_at = ?2, extraction_error = ?3 WHERE id = ?4",
+            params![text, current_date, extraction_error, id],
+        ).map_err(|e| e.to_string())?;
+
+        let _ = app.emit_all(PROGRESS_EVENT, ExtractionProgress {
+            attachment_id: id,
+            original_name,
+            processed: processed + 1,
+            total,
+            error: extraction_error,
+        });
+    }
+
+    let _ = app.emit_all(FINISHED_EVENT, ExtractionFinished { extracted: total - failed, failed });
+    Ok(())
+}
+
+// Lance l'extracteur : un passage au démarrage, puis à chaque demande ou après RESCAN_INTERVAL
+pub(crate) fn start(app: AppHandle) -> TextExtractor {
+    let (wake, requests) = mpsc::channel();
+    thread::spawn(move || loop {
+        if let Err(e) = extract_pending(&app) {
+            error!("Text extraction failed: {}", e);
+        }
+        match requests.recv_timeout(RESCAN_INTERVAL) {
+            Ok(()) | Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    });
+    TextExtractor { wake: Mutex::new(wake) }
+}
+
+impl TextExtractor {
+    // Demande un passage immédiat de l'extracteur
+    pub(crate) fn wake(&self) -> Result<(), String> {
+        self.wake.lock().map_err(|e| e.to_string())?.send(()).map_err(|e| e.to_string())
+    }
+}
+
+// Demande une extraction immédiate et renvoie le nombre de pièces jointes en attente
+#[command]
+pub fn extract_attachment_texts(extractor: State<'_, TextExtractor>) -> Result<i64, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let pending = conn
+        .query_row(&format!("SELECT COUNT(*) FROM attachments WHERE {}", PENDING_FILTER), [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    extractor.wake()?;
+    Ok(pending)
+}
@@ -0,0 +1,412 @@
+// ------------------------------------ CSV import -----------------------------------------
+//
+// Import de factures, revenus et tâches depuis un CSV (par exemple un export du Dashboard).
+// Toutes les lignes sont traitées dans une seule transaction : en mode "dry run" elle est
+// annulée à la fin, ce qui donne exactement l'aperçu de ce qu'un import réel produirait.
+// Les fichiers copiés dans le coffre sont retirés si la transaction n'est pas validée.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::attachments::{attach_stored_file, replace_task_attachments};
+use crate::retention::remove_unreferenced_files;
+use crate::vault::{resolve_file_reference, split_paths, store_in_vault, vault_year};
+use crate::{completion_date, find_duplicate_invoices, hash_file, DuplicateCandidate};
+
+#[derive(Deserialize)]
+pub struct CsvImportOptions {
+    // "invoices", "revenues" ou "tasks"
+    record_type: String,
+    // Champ de la table -> en-tête de colonne du CSV
+    mapping: HashMap<String, String>,
+    delimiter: Option<char>,
+    // Format chrono des dates du fichier, "%Y-%m-%d" par défaut
+    date_format: Option<String>,
+    decimal_separator: Option<char>,
+    thousands_separator: Option<char>,
+    // Crée les créanciers, catégories, sources... inconnus au lieu de rejeter la ligne
+    create_missing: Option<bool>,
+    // Importe aussi les lignes signalées comme doublons de factures existantes
+    allow_duplicates: Option<bool>,
+    // Factures et revenus importés comme brouillons à relire dans l'inbox (par défaut)
+    as_drafts: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct ImportRowResult {
+    line: usize,
+    values: HashMap<String, String>,
+    errors: Vec<String>,
+    duplicates: Vec<DuplicateCandidate>,
+}
+
+#[derive(Serialize)]
+pub struct ImportReport {
+    dry_run: bool,
+    total: usize,
+    valid: usize,
+    imported: usize,
+    created_lookups: Vec<String>,
+    rows: Vec<ImportRowResult>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FieldKind {
+    Text,
+    Number,
+    Date,
+}
+
+struct FieldSpec {
+    name: &'static str,
+    kind: FieldKind,
+    required: bool,
+    // Table de référence dans laquelle la valeur doit exister
+    lookup: Option<&'static str>,
+}
+
+const fn field(name: &'static str, kind: FieldKind, required: bool, lookup: Option<&'static str>) -> FieldSpec {
+    FieldSpec { name, kind, required, lookup }
+}
+
+const INVOICE_FIELDS: &[FieldSpec] = &[
+    field("creditor", FieldKind::Text, true, Some("creditors")),
+    field("concern", FieldKind::Text, true, Some("concerns")),
+    field("category", FieldKind::Text, true, Some("categories")),
+    field("amount", FieldKind::Number, true, None),
+    field("due_date", FieldKind::Date, true, None),
+    field("path", FieldKind::Text, false, None),
+    field("description", FieldKind::Text, false, None),
+    field("status", FieldKind::Text, false, None),
+    field("payment_date", FieldKind::Date, false, None),
+    field("reference", FieldKind::Text, false, None),
+];
+
+const REVENUE_FIELDS: &[FieldSpec] = &[
+    field("source", FieldKind::Text, true, Some("sources")),
+    field("revenue_type", FieldKind::Text, true, Some("revenue_types")),
+    field("revenue_amount", FieldKind::Number, true, None),
+    field("receipt_date", FieldKind::Date, true, None),
+    field("revenue_description", FieldKind::Text, false, None),
+    field("revenue_path", FieldKind::Text, false, None),
+];
+
+const TASK_FIELDS: &[FieldSpec] = &[
+    field("title", FieldKind::Text, true, None),
+    field("description", FieldKind::Text, false, None),
+    field("status", FieldKind::Text, true, Some("task_statuses")),
+    field("priority", FieldKind::Text, true, Some("task_priorities")),
+    field("due_date", FieldKind::Date, false, None),
+    field("category", FieldKind::Text, false, Some("task_categories")),
+    field("attachments", FieldKind::Text, false, None),
+];
+
+// Champs contenant des chemins de fichiers (les pièces jointes des tâches sont une liste "a;b;c")
+const FILE_FIELDS: &[(&str, bool)] = &[("path", false), ("revenue_path", false), ("attachments", true)];
+
+fn fields_for(record_type: &str) -> Result<&'static [FieldSpec], String> {
+    match record_type {
+        "invoices" => Ok(INVOICE_FIELDS),
+        "revenues" => Ok(REVENUE_FIELDS),
+        "tasks" => Ok(TASK_FIELDS),
+        other => Err(format!("Unsupported record type: {}", other)),
+    }
+}
+
+// Convertit un nombre saisi avec des séparateurs locaux ("1'234,50", "1 234.50") en f64
+fn parse_number(raw: &str, decimal_separator: char, thousands_separator: Option<char>) -> Result<f64, String> {
+    let cleaned: String = raw
+        .chars()
+        .filter(|c| Some(*c) != thousands_separator && !c.is_whitespace())
+        .map(|c| if c == decimal_separator { '.' } else { c })
+        .collect();
+    cleaned.parse::<f64>().map_err(|_| format!("invalid number '{}'", raw))
+}
+
+// Renvoie le nom tel qu'enregistré dans la table de référence, en le créant si demandé
+fn resolve_lookup(
+    conn: &Connection,
+    table: &str,
+    value: &str,
+    create_missing: bool,
+    created: &mut Vec<String>
+) -> Result<Option<String>, String> {
+    let existing: Option<String> = conn
+        .query_row(
+            &format!("SELECT name FROM {} WHERE name = ?1 COLLATE NOCASE", table),
+            params![value],
+            |row| row.get(0),
+        )
+        .ok();
+    if existing.is_some() {
+        return Ok(existing);
+    }
+    if !create_missing {
+        return Ok(None);
+    }
+    conn.execute(&format!("INSERT INTO {} (name) VALUES (?1)", table), params![value])
+        .map_err(|e| e.to_string())?;
+    created.push(format!("{}: {}", table, value));
+    Ok(Some(value.to_string()))
+}
+
+// Range un fichier référencé par une ligne dans le coffre ; en "dry run" le chemin est laissé tel quel
+fn vault_path(
+    path: Option<String>,
+    record_type: &str,
+    date: Option<String>,
+    store_files: bool,
+    stored: &mut Vec<String>
+) -> Result<Option<String>, String> {
+    match path {
+        Some(p) if store_files => {
+            let reference = store_in_vault(&p, record_type, vault_year(date.as_deref()), false)?;
+            stored.push(reference.clone());
+            Ok(Some(reference))
+        }
+        other => Ok(other),
+    }
+}
+
+fn insert_row(
+    conn: &Connection,
+    record_type: &str,
+    values: &HashMap<String, String>,
+    store_files: bool,
+    is_draft: bool,
+    stored: &mut Vec<String>
+) -> Result<(), String> {
+    let text = |name: &str| values.get(name).cloned();
+    let number = |name: &str| values.get(name).and_then(|v| v.parse::<f64>().ok());
+
+    match record_type {
+        "invoices" => {
+            let file_hash = match text("path") {
+                Some(p) => Some(hash_file(resolve_file_reference(&p)?)?),
+                None => None,
+            };
+            let path = vault_path(text("path"), "invoices", text("due_date"), store_files, stored)?;
+            conn.execute(
+                "INSERT INTO invoices (creditor, concern, category, amount, due_date, path, description, status, payment_date, reference, file_hash, is_draft) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                params![
+                    text("creditor"), text("concern"), text("category"), number("amount"), text("due_date"),
+                    path, text("description"), text("status").unwrap_or_else(|| "Open".to_string()),
+                    text("payment_date"), text("reference"), file_hash, is_draft
+                ],
+            ).map_err(|e| e.to_string())?;
+            if let (Some(reference), Some(original)) = (path, text("path")) {
+                attach_stored_file(conn, "invoices", conn.last_insert_rowid(), &reference, &original)?;
+            }
+        }
+        "revenues" => {
+            let revenue_path = vault_path(text("revenue_path"), "revenues", text("receipt_date"), store_files, stored)?;
+            conn.execute(
+                "INSERT INTO revenues (source, revenue_type, revenue_amount, receipt_date, revenue_description, revenue_path, is_draft) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    text("source"), text("revenue_type"), number("revenue_amount"), text("receipt_date"),
+                    text("revenue_description"), revenue_path, is_draft
+                ],
+            ).map_err(|e| e.to_string())?;
+            if let (Some(reference), Some(original)) = (revenue_path, text("revenue_path")) {
+                attach_stored_file(conn, "revenues", conn.last_insert_rowid(), &reference, &original)?;
+            }
+        }
+        _ => {
+            let current_date = chrono::Local::now().naive_local().to_string();
+            conn.execute(
+                "INSERT INTO tasks (title, description, status, priority, due_date, creation_date, category, attachments, updated_at, completed_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?6, ?9)",
+                params![
+                    text("title"), text("description").unwrap_or_default(), text("status"), text("priority"),
+                    text("due_date").unwrap_or_default(), current_date, text("category").unwrap_or_default(), text("attachments"),
+                    completion_date(conn, None, &text("status").unwrap_or_default(), &current_date)?
+                ],
+            ).map_err(|e| e.to_string())?;
+            if store_files {
+                let mut references = Vec::new();
+                for p in split_paths(&text("attachments").unwrap_or_default(), true) {
+                    references.extend(vault_path(Some(p.to_string()), "tasks", text("due_date"), true, stored)?);
+                }
+                replace_task_attachments(conn, conn.last_insert_rowid(), Some(&references.join(";")))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[command]
+pub fn import_csv(path: String, options: CsvImportOptions, dry_run: bool) -> Result<ImportReport, String> {
+    let specs = fields_for(&options.record_type)?;
+    for spec in specs.iter().filter(|s| s.required) {
+        if !options.mapping.contains_key(spec.name) {
+            return Err(format!("Missing column mapping for required field '{}'", spec.name));
+        }
+    }
+
+    let date_format = options.date_format.clone().unwrap_or_else(|| "%Y-%m-%d".to_string());
+    let decimal_separator = options.decimal_separator.unwrap_or('.');
+    let create_missing = options.create_missing.unwrap_or(false);
+    let allow_duplicates = options.allow_duplicates.unwrap_or(false);
+    let as_drafts = options.as_drafts.unwrap_or(true);
+
+    let delimiter = options.delimiter.unwrap_or(',');
+    if !delimiter.is_ascii() {
+        return Err(format!("The delimiter '{}' must be an ASCII character", delimiter));
+    }
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter as u8)
+        .flexible(true)
+        .from_path(&path)
+        .map_err(|e| e.to_string())?;
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+
+    // Index de colonne pour chaque champ mappé
+    let mut columns = HashMap::new();
+    for (field_name, header) in &options.mapping {
+        if !specs.iter().any(|s| s.name == field_name) {
+            return Err(format!("Unknown field '{}' for {}", field_name, options.record_type));
+        }
+        let index = headers
+            .iter()
+            .position(|h| h.trim() == header.trim())
+            .ok_or_else(|| format!("Column '{}' not found in {}", header, path))?;
+        columns.insert(field_name.as_str(), index);
+    }
+
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let mut rows = Vec::new();
+    let mut created_lookups = Vec::new();
+    let mut imported = 0;
+    let mut stored = Vec::new();
+
+    // Les erreurs ne quittent pas la fonction avant le nettoyage du coffre ci-dessous
+    let result = (|| -> Result<(), String> {
+        for (index, record) in reader.records().enumerate() {
+            // Ligne 1 = en-têtes
+            let line = index + 2;
+            let mut values = HashMap::new();
+            let mut errors = Vec::new();
+            let mut duplicates = Vec::new();
+
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    rows.push(ImportRowResult { line, values, errors: vec![e.to_string()], duplicates });
+                    continue;
+                }
+            };
+
+            for spec in specs {
+                let raw = columns
+                    .get(spec.name)
+                    .and_then(|i| record.get(*i))
+                    .map(str::trim)
+                    .unwrap_or("");
+                if raw.is_empty() {
+                    if spec.required {
+                        errors.push(format!("{}: missing value", spec.name));
+                    }
+                    continue;
+                }
+
+                let value = match spec.kind {
+                    FieldKind::Text => Ok(raw.to_string()),
+                    FieldKind::Number => parse_number(raw, decimal_separator, options.thousands_separator).map(|n| n.to_string()),
+                    FieldKind::Date => NaiveDate::parse_from_str(raw, &date_format)
+                        .map(|d| d.format("%Y-%m-%d").to_string())
+                        .map_err(|_| format!("invalid date '{}' (expected {})", raw, date_format)),
+                };
+                let value = match value {
+                    Ok(value) => value,
+                    Err(e) => {
+                        errors.push(format!("{}: {}", spec.name, e));
+                        continue;
+                    }
+                };
+
+                let value = match spec.lookup {
+                    Some(table) => match resolve_lookup(&tx, table, &value, create_missing, &mut created_lookups)? {
+                        Some(name) => name,
+                        None => {
+                            errors.push(format!("{}: unknown value '{}'", spec.name, value));
+                            continue;
+                        }
+                    },
+                    None => value,
+                };
+                values.insert(spec.name.to_string(), value);
+            }
+
+            // Un fichier manquant est signalé sur sa ligne, en "dry run" comme à l'import
+            for (field_name, multi) in FILE_FIELDS {
+                for file in split_paths(values.get(*field_name).map(String::as_str).unwrap_or_default(), *multi) {
+                    if !resolve_file_reference(file).is_ok_and(|p| p.is_file()) {
+                        errors.push(format!("{}: file not found '{}'", field_name, file));
+                    }
+                }
+            }
+
+            if errors.is_empty() && options.record_type == "invoices" {
+                let file_hash = match values.get("path") {
+                    Some(p) => match resolve_file_reference(p).and_then(hash_file) {
+                        Ok(hash) => Some(hash),
+                        Err(e) => {
+                            errors.push(format!("path: {}", e));
+                            None
+                        }
+                    },
+                    None => None,
+                };
+                if errors.is_empty() {
+                    // Les lignes déjà insérées dans la transaction comptent aussi : un doublon interne au fichier est détecté
+                    duplicates = find_duplicate_invoices(
+                        &tx,
+                        &values["creditor"],
+                        values["amount"].parse().unwrap_or_default(),
+                        &values["due_date"],
+                        values.get("reference").map(String::as_str),
+                        file_hash.as_deref(),
+                    )?;
+                    if !duplicates.is_empty() && !allow_duplicates {
+                        errors.push("possible duplicate of an existing invoice".to_string());
+                    }
+                }
+            }
+
+            if errors.is_empty() {
+                insert_row(&tx, &options.record_type, &values, !dry_run, as_drafts, &mut stored)?;
+                imported += 1;
+            }
+            rows.push(ImportRowResult { line, values, errors, duplicates });
+        }
+        Ok(())
+    })();
+
+    let result = match result {
+        Ok(()) if !dry_run => tx.commit().map_err(|e| e.to_string()),
+        other => {
+            drop(tx);
+            other
+        }
+    };
+    if let Err(e) = result {
+        // Import annulé : les copies faites dans le coffre ne sont plus référencées
+        remove_unreferenced_files(&conn, stored)?;
+        return Err(e);
+    }
+
+    Ok(ImportReport {
+        dry_run,
+        total: rows.len(),
+        valid: imported,
+        imported: if dry_run { 0 } else { imported },
+        created_lookups,
+        rows,
+    })
+}
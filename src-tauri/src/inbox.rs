@@ -0,0 +1,373 @@
+// ------------------------------------ Watched inbox -----------------------------------------
+//
+// Le courrier scanné est déposé dans un dossier surveillé. Chaque nouveau fichier devient un
+// brouillon de facture ou de document administratif selon le sous-dossier où il se trouve
+// ("inbox/invoices" → factures, "inbox/admin" → documents), est déplacé dans le coffre, et
+// l'interface est prévenue par un événement Tauri. Les e-mails (.eml, .mbox) donnent un brouillon
+// par message.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use log::{error, info};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::{command, AppHandle, Manager, State};
+
+use crate::attachments::attach_stored_file;
+use crate::extraction::{extract_text, TextExtractor};
+use crate::mail::{import_mail_file, is_mail_file};
+use crate::retention::remove_unreferenced_files;
+use crate::suggestions::suggest_from_text;
+use crate::vault::{file_name_of, store_in_vault, vault_year};
+use crate::versions::insert_version;
+use crate::{find_duplicate_invoices, get_setting, hash_file, is_same_file_duplicate, set_setting};
+
+const SCAN_INTERVAL: Duration = Duration::from_secs(30);
+// Un fichier modifié plus récemment est peut-être encore en cours d'écriture par le scanner
+const SETTLE_TIME: Duration = Duration::from_secs(10);
+const RECORD_CREATED_EVENT: &str = "inbox-record-created";
+const DIRECTORY_SETTING: &str = "inbox_directory";
+const DEFAULT_TYPE_SETTING: &str = "inbox_default_record_type";
+const RECORD_TYPES: &[&str] = &["invoices", "admin_documents"];
+// Fichiers temporaires des navigateurs et des logiciels de numérisation
+const TEMPORARY_EXTENSIONS: &[&str] = &["part", "tmp", "crdownload", "download"];
+
+pub struct InboxWatcher {
+    wake: Mutex<Sender<()>>,
+}
+
+impl InboxWatcher {
+    fn wake(&self) -> Result<(), String> {
+        self.wake.lock().map_err(|e| e.to_string())?.send(()).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Serialize)]
+pub struct InboxSettings {
+    directory: Option<String>,
+    default_record_type: String,
+    rules: Vec<InboxRule>,
+}
+
+#[derive(Serialize)]
+pub struct InboxRule {
+    id: i64,
+    subfolder: String,
+    record_type: String,
+    category: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+struct InboxRecordCreated {
+    record_type: String,
+    record_id: i64,
+    original_name: String,
+}
+
+fn check_record_type(record_type: &str) -> Result<(), String> {
+    if RECORD_TYPES.contains(&record_type) {
+        Ok(())
+    } else {
+        Err(format!("Unsupported record type '{}', expected one of {}", record_type, RECORD_TYPES.join(", ")))
+    }
+}
+
+fn query_rules(conn: &Connection) -> Result<Vec<InboxRule>, String> {
+    let mut stmt = conn.prepare("SELECT id, subfolder, record_type, category FROM inbox_rules ORDER BY subfolder")
+        .map_err(|e| e.to_string())?;
+    let rule_iter = stmt.query_map([], |row| {
+        Ok(InboxRule {
+            id: row.get(0)?,
+            subfolder: row.get(1)?,
+            record_type: row.get(2)?,
+            category: row.get(3)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut rules = Vec::new();
+    for rule in rule_iter {
+        rules.push(rule.map_err(|e| e.to_string())?);
+    }
+    Ok(rules)
+}
+
+fn is_ready(path: &Path) -> bool {
+    let name = file_name_of(&path.to_string_lossy());
+    let temporary = path
+        .extension()
+        .map(|e| TEMPORARY_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false);
+    let settled = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .map(|age| age >= SETTLE_TIME)
+        .unwrap_or(false);
+    !name.starts_with('.') && !temporary && settled
+}
+
+// Fichiers prêts du dossier surveillé (racine et sous-dossiers directs), avec le sous-dossier d'origine
+fn pending_files(directory: &Path) -> Result<Vec<(Option<String>, PathBuf)>, String> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(directory).map_err(|e| e.to_string())? {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if path.is_file() {
+            files.push((None, path));
+        } else if path.is_dir() {
+            let subfolder = file_name_of(&path.to_string_lossy());
+            for entry in fs::read_dir(&path).map_err(|e| e.to_string())? {
+                let path = entry.map_err(|e| e.to_string())?.path();
+                if path.is_file() {
+                    files.push((Some(subfolder.clone()), path));
+                }
+            }
+        }
+    }
+    files.retain(|(_, path)| is_ready(path));
+    Ok(files)
+}
+
+// Le fichier est copié dans le coffre (référence ajoutée à `stored`) ; la source n'est retirée qu'après le commit.
+// None : le fichier est déjà celui d'une facture, aucun brouillon n'est créé
+fn create_draft(conn: &Connection, path: &Path, record_type: &str, category: Option<&str>, stored: &mut Vec<String>) -> Result<Option<i64>, String> {
+    let source = path.to_string_lossy().to_string();
+    let original_name = file_name_of(&source);
+    let title = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| original_name.clone());
+    let now = chrono::Local::now().naive_local();
+
+    if record_type == "invoices" {
+        // Champs pré-remplis à partir du texte du fichier quand il est lisible
+        let mime_type = mime_guess::from_path(path).first_or_octet_stream().to_string();
+        let suggestion = extract_text(path, &mime_type).ok().map(|text| suggest_from_text(conn, &text)).transpose()?;
+        let suggestion = suggestion.unwrap_or_default();
+        let due_date = suggestion.due_date.unwrap_or_else(|| now.date().to_string());
+        let creditor = suggestion.creditor.unwrap_or_default();
+        let amount = suggestion.amount.unwrap_or_default();
+
+        // Les autres ressemblances sont signalées dans l'inbox des brouillons
+        let file_hash = hash_file(path)?;
+        let duplicates = find_duplicate_invoices(conn, &creditor, amount, &due_date, suggestion.reference.as_deref(), Some(&file_hash))?;
+        if is_same_file_duplicate(&duplicates) {
+            return Ok(None);
+        }
+
+        let reference = store_in_vault(&source, "invoices", vault_year(Some(&due_date)), false)?;
+        stored.push(reference.clone());
+        conn.execute(
+            "INSERT INTO invoices (creditor, concern, category, amount, due_date, path, description, status, reference, file_hash, is_draft) VALUES (?1, '', ?2, ?3, ?4, ?5, ?6, 'Open', ?7, ?8, 1)",
+            params![
+                creditor, category.unwrap_or_default(), amount,
+                due_date, reference, title, suggestion.reference, file_hash
+            ],
+        ).map_err(|e| e.to_string())?;
+        let id = conn.last_insert_rowid();
+        attach_stored_file(conn, "invoices", id, &reference, &source)?;
+        Ok(Some(id))
+    } else {
+        let reference = store_in_vault(&source, "admin_documents", vault_year(None), false)?;
+        stored.push(reference.clone());
+        conn.execute(
+            "INSERT INTO admin_documents (admin_doc_concern, admin_doc_category, admin_doc_description, admin_doc_status, admin_doc_path, lifecycle_status, received_at, is_draft) VALUES ('', ?1, ?2, 'Pending', ?3, 'received', ?4, 1)",
+            params![category.unwrap_or_default(), title, reference, now.to_string()],
+        ).map_err(|e| e.to_string())?;
+        let id = conn.last_insert_rowid();
+        attach_stored_file(conn, "admin_documents", id, &reference, &source)?;
+        insert_version(conn, id, &reference, &original_name, None)?;
+        Ok(Some(id))
+    }
+}
+
+// Vrai si ce contenu a déjà été importé depuis l'inbox ou est déjà joint à un enregistrement
+fn is_processed(conn: &Connection, sha256: &str) -> Result<bool, String> {
+    conn.prepare(
+        "SELECT 1 FROM inbox_processed_files WHERE sha256 = ?1
+         UNION ALL SELECT 1 FROM attachments WHERE sha256 = ?1 LIMIT 1"
+    )
+    .and_then(|mut stmt| stmt.exists(params![sha256]))
+    .map_err(|e| e.to_string())
+}
+
+fn mark_processed(conn: &Connection, sha256: &str, path: &Path) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR IGNORE INTO inbox_processed_files (sha256, original_name, processed_at) VALUES (?1, ?2, ?3)",
+        params![sha256, file_name_of(&path.to_string_lossy()), chrono::Local::now().naive_local().to_string()],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Un fichier importé qui ne peut pas être supprimé (partage en lecture seule, fichier verrouillé)
+// reste dans le dossier ; son empreinte évite qu'il soit réimporté à chaque passage
+fn leave_inbox(path: &Path) {
+    if let Err(e) = fs::remove_file(path) {
+        error!("{} was imported but could not be removed from the inbox: {}", path.display(), e);
+    }
+}
+
+// Brouillons créés pour un fichier de l'inbox, qui quitte ensuite le dossier
+fn import_file(conn: &mut Connection, path: &Path, record_type: &str, category: Option<&str>) -> Result<Vec<i64>, String> {
+    let sha256 = hash_file(path)?;
+    if is_processed(conn, &sha256)? {
+        // Déjà traité : nouvelle tentative de suppression, sans message à chaque passage
+        let _ = fs::remove_file(path);
+        return Ok(Vec::new());
+    }
+
+    if is_mail_file(path) {
+        let imported = import_mail_file(conn, path, record_type, category)?;
+        mark_processed(conn, &sha256, path)?;
+        leave_inbox(path);
+        Ok(imported.iter().filter_map(|email| email.record_id).collect())
+    } else {
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let mut stored = Vec::new();
+        let created = create_draft(&tx, path, record_type, category, &mut stored)
+            .and_then(|id| mark_processed(&tx, &sha256, path).map(|_| id))
+            .and_then(|id| tx.commit().map(|_| id).map_err(|e| e.to_string()));
+        match created {
+            Ok(id) => {
+                // Un fichier déjà enregistré quitte aussi le dossier : son contenu est dans le coffre
+                if id.is_none() {
+                    info!("{} is already attached to an invoice, no draft created", path.display());
+                }
+                leave_inbox(path);
+                Ok(id.into_iter().collect())
+            }
+            Err(e) => {
+                // Rien n'a été enregistré : la copie dans le coffre est retirée si personne d'autre ne l'utilise
+                remove_unreferenced_files(conn, stored)?;
+                Err(e)
+            }
+        }
+    }
+}
+
+fn scan_inbox(app: &AppHandle) -> Result<(), String> {
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let directory = match get_setting(&conn, DIRECTORY_SETTING).map_err(|e| e.to_string())? {
+        Some(directory) => PathBuf::from(directory),
+        None => return Ok(()),
+    };
+    if !directory.is_dir() {
+        return Err(format!("Inbox directory not found: {}", directory.display()));
+    }
+    let default_record_type = get_setting(&conn, DEFAULT_TYPE_SETTING)
+        .map_err(|e| e.to_string())?
+        .unwrap_or_else(|| "admin_documents".to_string());
+    let rules = query_rules(&conn)?;
+
+    let mut created = 0;
+    for (subfolder, path) in pending_files(&directory)? {
+        let rule = subfolder
+            .as_ref()
+            .and_then(|s| rules.iter().find(|r| r.subfolder.eq_ignore_ascii_case(s)));
+        let record_type = rule.map(|r| r.record_type.as_str()).unwrap_or(&default_record_type);
+        let category = rule.and_then(|r| r.category.as_deref());
+
+        // Un fichier qui échoue reste dans le dossier et sera repris au prochain passage
+        match import_file(&mut conn, &path, record_type, category) {
+            Ok(record_ids) => {
+                for record_id in record_ids {
+                    created += 1;
+                    let _ = app.emit_all(RECORD_CREATED_EVENT, InboxRecordCreated {
+                        record_type: record_type.to_string(),
+                        record_id,
+                        original_name: file_name_of(&path.to_string_lossy()),
+                    });
+                }
+            }
+            Err(e) => error!("Failed to import {} from the inbox: {}", path.display(), e),
+        }
+    }
+
+    if created > 0 {
+        app.state::<TextExtractor>().wake()?;
+    }
+    Ok(())
+}
+
+// Lance la surveillance : un passage au démarrage, puis après chaque réglage ou SCAN_INTERVAL
+pub(crate) fn start(app: AppHandle) -> InboxWatcher {
+    let (wake, requests) = mpsc::channel();
+    thread::spawn(move || loop {
+        if let Err(e) = scan_inbox(&app) {
+            error!("Inbox scan failed: {}", e);
+        }
+        match requests.recv_timeout(SCAN_INTERVAL) {
+            Ok(()) | Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    });
+    InboxWatcher { wake: Mutex::new(wake) }
+}
+
+#[command]
+pub fn get_inbox_settings() -> Result<InboxSettings, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    Ok(InboxSettings {
+        directory: get_setting(&conn, DIRECTORY_SETTING).map_err(|e| e.to_string())?,
+        default_record_type: get_setting(&conn, DEFAULT_TYPE_SETTING)
+            .map_err(|e| e.to_string())?
+            .unwrap_or_else(|| "admin_documents".to_string()),
+        rules: query_rules(&conn)?,
+    })
+}
+
+#[command]
+pub fn set_inbox_settings(
+    directory: Option<String>,
+    default_record_type: String,
+    watcher: State<'_, InboxWatcher>
+) -> Result<(), String> {
+    check_record_type(&default_record_type)?;
+    let directory = directory.filter(|d| !d.trim().is_empty());
+    if let Some(directory) = &directory {
+        if !Path::new(directory).is_dir() {
+            return Err(format!("Directory not found: {}", directory));
+        }
+    }
+
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    set_setting(&conn, DIRECTORY_SETTING, directory.as_deref()).map_err(|e| e.to_string())?;
+    set_setting(&conn, DEFAULT_TYPE_SETTING, Some(&default_record_type)).map_err(|e| e.to_string())?;
+    watcher.wake()
+}
+
+#[command]
+pub fn add_inbox_rule(subfolder: String, record_type: String, category: Option<String>) -> Result<(), String> {
+    check_record_type(&record_type)?;
+    let subfolder = subfolder.trim().trim_matches(['/', '\\']).to_string();
+    if subfolder.is_empty() || subfolder.contains(['/', '\\']) {
+        return Err("The subfolder must be a direct subfolder of the inbox".to_string());
+    }
+
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM inbox_rules WHERE subfolder = ?1 COLLATE NOCASE",
+        params![subfolder],
+    ).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO inbox_rules (subfolder, record_type, category) VALUES (?1, ?2, ?3)",
+        params![subfolder, record_type, category.filter(|c| !c.trim().is_empty())],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[command]
+pub fn delete_inbox_rule(id: i64) -> Result<(), String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM inbox_rules WHERE id = ?1",
+        params![id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[command]
+pub fn scan_inbox_now(watcher: State<'_, InboxWatcher>) -> Result<(), String> {
+    watcher.wake()
+}
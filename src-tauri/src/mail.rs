@@ -0,0 +1,372 @@
+// ------------------------------------ E-mail import -----------------------------------------
+//
+// Beaucoup de factures arrivent par e-mail. Un fichier .eml (un message) ou .mbox (une boîte
+// entière) devient un brouillon de facture ou de document administratif par message : l'expéditeur
+// désigne le créancier, les pièces jointes et le message d'origine sont rangés dans le coffre.
+
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use chrono::TimeZone;
+use mailparse::{addrparse_header, dateparse, parse_mail, DispositionType, MailAddr, MailHeaderMap, ParsedMail};
+use regex::Regex;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::{command, State};
+
+use crate::attachments::insert_attachment;
+use crate::extraction::{extract_text, TextExtractor};
+use crate::retention::remove_unreferenced_files;
+use crate::suggestions::suggest_from_text;
+use crate::vault::{file_name_of, resolve_file_reference, store_bytes_in_vault, vault_year};
+use crate::versions::insert_version;
+use crate::{find_duplicate_invoices, is_same_file_duplicate, DuplicateCandidate};
+
+const MAIL_EXTENSIONS: &[&str] = &["eml", "mbox"];
+const RECORD_TYPES: &[&str] = &["invoices", "admin_documents"];
+
+static HIDDEN_HTML_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<(style|script)\b.*?</(style|script)>").expect("invalid built-in pattern"));
+static HTML_TAG_PATTERN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<[^>]*>").expect("invalid built-in pattern"));
+
+#[derive(Serialize)]
+pub struct ImportedEmail {
+    record_type: String,
+    // None : facture déjà enregistrée (même fichier), aucun brouillon créé
+    pub(crate) record_id: Option<i64>,
+    sender: Option<String>,
+    subject: String,
+    creditor: Option<String>,
+    attachments: usize,
+    // Factures existantes qui ressemblent à celle-ci
+    duplicates: Vec<DuplicateCandidate>,
+}
+
+struct MailAttachment {
+    file_name: String,
+    content: Vec<u8>,
+}
+
+struct Email {
+    sender_name: Option<String>,
+    sender_address: Option<String>,
+    subject: String,
+    date: Option<String>,
+    body: String,
+    attachments: Vec<MailAttachment>,
+    raw: Vec<u8>,
+}
+
+pub(crate) fn is_mail_file(path: &Path) -> bool {
+    path.extension()
+        .map(|e| MAIL_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+// Découpe une boîte mbox : chaque message commence par une ligne "From " précédée d'une ligne vide
+fn split_mbox(content: &[u8]) -> Vec<Vec<u8>> {
+    let mut messages = Vec::new();
+    let mut current: Option<Vec<u8>> = None;
+    let mut previous_blank = true;
+
+    for line in content.split_inclusive(|b| *b == b'\n') {
+        if previous_blank && line.starts_with(b"From ") {
+            messages.extend(current.take());
+            current = Some(Vec::new());
+        } else if let Some(message) = current.as_mut() {
+            // mboxrd : ">From " (et ">>From "…) a été échappé à l'écriture
+            let unescaped = line.iter().position(|b| *b != b'>').filter(|i| *i > 0 && line[*i..].starts_with(b"From "));
+            message.extend_from_slice(if unescaped.is_some() { &line[1..] } else { line });
+        }
+        previous_blank = line.iter().all(|b| b.is_ascii_whitespace());
+    }
+    messages.extend(current);
+    messages.retain(|m| !m.iter().all(|b| b.is_ascii_whitespace()));
+    messages
+}
+
+fn strip_html(html: &str) -> String {
+    let html = HIDDEN_HTML_PATTERN.replace_all(html, " ");
+    let text = HTML_TAG_PATTERN.replace_all(&html, " ");
+    text.replace("&nbsp;", " ").replace("&amp;", "&").replace("&lt;", "<").replace("&gt;", ">")
+}
+
+// Parcourt les parties du message : texte du corps (de préférence text/plain) et pièces jointes
+fn collect_parts(part: &ParsedMail, plain: &mut Vec<String>, html: &mut Vec<String>, attachments: &mut Vec<MailAttachment>) -> Result<(), String> {
+    if !part.subparts.is_empty() {
+        for subpart in &part.subparts {
+            collect_parts(subpart, plain, html, attachments)?;
+        }
+        return Ok(());
+    }
+
+    let disposition = part.get_content_disposition();
+    let file_name = disposition.params.get("filename").or_else(|| part.ctype.params.get("name"));
+    if let Some(file_name) = file_name.filter(|_| disposition.disposition != DispositionType::Inline || !part.ctype.mimetype.starts_with("text/")) {
+        attachments.push(MailAttachment {
+            file_name: file_name_of(file_name),
+            content: part.get_body_raw().map_err(|e| e.to_string())?,
+        });
+    } else if part.ctype.mimetype == "text/plain" {
+        plain.push(part.get_body().map_err(|e| e.to_string())?);
+    } else if part.ctype.mimetype == "text/html" {
+        html.push(strip_html(&part.get_body().map_err(|e| e.to_string())?));
+    }
+    Ok(())
+}
+
+fn parse_email(raw: Vec<u8>) -> Result<Email, String> {
+    let mail = parse_mail(&raw).map_err(|e| e.to_string())?;
+
+    let sender = mail.headers
+        .get_first_header("From")
+        .and_then(|header| addrparse_header(header).ok())
+        .and_then(|addresses| addresses.iter().find_map(|address| match address {
+            MailAddr::Single(info) => Some(info.clone()),
+            MailAddr::Group(group) => group.addrs.first().cloned(),
+        }));
+    let date = mail.headers
+        .get_first_value("Date")
+        .and_then(|d| dateparse(&d).ok())
+        .and_then(|timestamp| chrono::Local.timestamp_opt(timestamp, 0).single())
+        .map(|d| d.date_naive().to_string());
+
+    let (mut plain, mut html, mut attachments) = (Vec::new(), Vec::new(), Vec::new());
+    collect_parts(&mail, &mut plain, &mut html, &mut attachments)?;
+    let body = if plain.is_empty() { html } else { plain };
+
+    Ok(Email {
+        sender_name: sender.as_ref().and_then(|s| s.display_name.clone()),
+        sender_address: sender.map(|s| s.addr.to_lowercase()),
+        subject: mail.headers.get_first_value("Subject").unwrap_or_default().trim().to_string(),
+        date,
+        body: body.join("\n").split_whitespace().collect::<Vec<_>>().join(" "),
+        attachments,
+        raw,
+    })
+}
+
+// Créancier dont l'adresse, ou à défaut le domaine, correspond à l'expéditeur
+fn match_sender(conn: &Connection, address: &str) -> Result<Option<String>, String> {
+    let mut stmt = conn.prepare("SELECT name, email FROM creditors WHERE email IS NOT NULL").map_err(|e| e.to_string())?;
+    let creditors: Vec<(String, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<_>>()
+        .map_err(|e| e.to_string())?;
+
+    if let Some((name, _)) = creditors.iter().find(|(_, email)| email.eq_ignore_ascii_case(address)) {
+        return Ok(Some(name.clone()));
+    }
+    // "edf.fr" reconnaît aussi "factures@clients.edf.fr" ; le domaine le plus précis l'emporte
+    let domain = address.rsplit('@').next().unwrap_or_default();
+    Ok(creditors
+        .into_iter()
+        .filter(|(_, email)| !email.contains('@'))
+        .filter(|(_, email)| domain == email.as_str() || domain.ends_with(&format!(".{}", email)))
+        .max_by_key(|(_, email)| email.len())
+        .map(|(name, _)| name))
+}
+
+fn check_record_type(record_type: &str) -> Result<(), String> {
+    if RECORD_TYPES.contains(&record_type) {
+        Ok(())
+    } else {
+        Err(format!("Unsupported record type '{}', expected one of {}", record_type, RECORD_TYPES.join(", ")))
+    }
+}
+
+// Chaque copie faite dans le coffre est ajoutée à `stored`, pour être retirée si l'import échoue
+fn import_email(
+    conn: &Connection,
+    email: Email,
+    record_type: &str,
+    category: Option<&str>,
+    stored: &mut Vec<String>
+) -> Result<ImportedEmail, String> {
+    let today = chrono::Local::now().naive_local().date().to_string();
+    let year = vault_year(email.date.as_deref());
+
+    // Pièces jointes d'abord, le message d'origine en dernier : la première pièce jointe est le document
+    let mut files = Vec::new();
+    for attachment in &email.attachments {
+        let reference = store_bytes_in_vault(&attachment.content, &attachment.file_name, record_type, year)?;
+        stored.push(reference.clone());
+        files.push((reference, attachment.file_name.clone()));
+    }
+    let message_name = format!("{}.eml", if email.subject.is_empty() { "message" } else { &email.subject })
+        .replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_");
+    let message_reference = store_bytes_in_vault(&email.raw, &message_name, record_type, year)?;
+    stored.push(message_reference.clone());
+    files.push((message_reference, message_name));
+    let (primary_reference, primary_name) = files[0].clone();
+
+    let sender_creditor = match &email.sender_address {
+        Some(address) => match_sender(conn, address)?,
+        None => None,
+    };
+    let description = if email.subject.is_empty() { primary_name.clone() } else { email.subject.clone() };
+
+    let (record_id, creditor, duplicates) = if record_type == "invoices" {
+        // Le montant et l'échéance se trouvent dans le message ou dans les PDF joints
+        let mut text = vec![email.subject.clone(), email.body.clone()];
+        for (reference, name) in &files[..files.len() - 1] {
+            let mime_type = mime_guess::from_path(name).first_or_octet_stream().to_string();
+            if let Ok(extracted) = resolve_file_reference(reference).and_then(|path| extract_text(&path, &mime_type)) {
+                text.push(extracted);
+            }
+        }
+        let suggestion = suggest_from_text(conn, &text.join("\n"))?;
+        let creditor = sender_creditor.or(suggestion.creditor);
+        let due_date = suggestion.due_date.or_else(|| email.date.clone()).unwrap_or_else(|| today.clone());
+        let amount = suggestion.amount.unwrap_or_default();
+
+        let primary_content = email.attachments.first().map_or(&email.raw, |a| &a.content);
+        let file_hash = format!("{:x}", Sha256::digest(primary_content));
+        let duplicates = find_duplicate_invoices(
+            conn, creditor.as_deref().unwrap_or_default(), amount, &due_date, suggestion.reference.as_deref(), Some(&file_hash)
+        )?;
+        if is_same_file_duplicate(&duplicates) {
+            // Les fichiers que l'on vient de copier ne servent à aucun enregistrement
+            remove_unreferenced_files(conn, files.into_iter().map(|(reference, _)| reference).collect())?;
+            return Ok(ImportedEmail {
+                record_type: record_type.to_string(),
+                record_id: None,
+                sender: email.sender_address,
+                subject: email.subject,
+                creditor,
+                attachments: email.attachments.len(),
+                duplicates,
+            });
+        }
+
+        conn.execute(
+            "INSERT INTO invoices (creditor, concern, category, amount, due_date, path, description, status, reference, file_hash, is_draft) VALUES (?1, '', ?2, ?3, ?4, ?5, ?6, 'Open', ?7, ?8, 1)",
+            params![
+                creditor.clone().unwrap_or_default(), category.unwrap_or_default(), amount,
+                due_date, primary_reference, description, suggestion.reference, file_hash
+            ],
+        ).map_err(|e| e.to_string())?;
+        (conn.last_insert_rowid(), creditor, duplicates)
+    } else {
+        let concern = sender_creditor.clone().or_else(|| email.sender_name.clone()).unwrap_or_default();
+        conn.execute(
+            "INSERT INTO admin_documents (admin_doc_concern, admin_doc_category, admin_doc_description, admin_doc_status, admin_doc_path, lifecycle_status, received_at, issue_date, is_draft) VALUES (?1, ?2, ?3, 'Pending', ?4, 'received', ?5, ?6, 1)",
+            params![
+                concern, category.unwrap_or_default(), description, primary_reference,
+                chrono::Local::now().naive_local().to_string(), email.date
+            ],
+        ).map_err(|e| e.to_string())?;
+        let id = conn.last_insert_rowid();
+        insert_version(conn, id, &primary_reference, &primary_name, None)?;
+        (id, sender_creditor, Vec::new())
+    };
+
+    for (reference, name) in &files {
+        insert_attachment(conn, record_type, record_id, reference, name, &today).map_err(|e| e.to_string())?;
+    }
+
+    Ok(ImportedEmail {
+        record_type: record_type.to_string(),
+        record_id: Some(record_id),
+        sender: email.sender_address,
+        subject: email.subject,
+        creditor,
+        attachments: email.attachments.len(),
+        duplicates,
+    })
+}
+
+// Importe tous les messages d'un fichier .eml ou .mbox, dans une seule transaction
+pub(crate) fn import_mail_file(conn: &mut Connection, path: &Path, record_type: &str, category: Option<&str>) -> Result<Vec<ImportedEmail>, String> {
+    check_record_type(record_type)?;
+    let content = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let is_mbox = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("mbox"));
+    let messages = if is_mbox { split_mbox(&content) } else { vec![content] };
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut stored = Vec::new();
+    let result = (|| -> Result<Vec<ImportedEmail>, String> {
+        let mut imported = Vec::new();
+        for raw in messages {
+            let email = parse_email(raw).map_err(|e| format!("{}: {}", path.display(), e))?;
+            imported.push(import_email(&tx, email, record_type, category, &mut stored)?);
+        }
+        Ok(imported)
+    })();
+
+    let result = match result {
+        Ok(imported) => tx.commit().map(|_| imported).map_err(|e| e.to_string()),
+        Err(e) => {
+            drop(tx);
+            Err(e)
+        }
+    };
+    if result.is_err() {
+        // Import annulé : les pièces jointes et messages copiés dans le coffre ne sont plus référencés
+        remove_unreferenced_files(conn, stored)?;
+    }
+    result
+}
+
+#[command]
+pub fn import_emails(
+    paths: Vec<String>,
+    record_type: String,
+    category: Option<String>,
+    extractor: State<'_, TextExtractor>
+) -> Result<Vec<ImportedEmail>, String> {
+    check_record_type(&record_type)?;
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let category = category.filter(|c| !c.trim().is_empty());
+
+    if let Some(path) = paths.iter().find(|p| !is_mail_file(Path::new(p))) {
+        return Err(format!("Not an e-mail file (.eml or .mbox): {}", path));
+    }
+
+    let mut imported = Vec::new();
+    for path in &paths {
+        imported.extend(import_mail_file(&mut conn, Path::new(path), &record_type, category.as_deref())?);
+    }
+
+    // Le texte des pièces jointes rejoint l'index de recherche
+    extractor.wake()?;
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(content: &str) -> Vec<String> {
+        split_mbox(content.as_bytes()).into_iter().map(|m| String::from_utf8(m).unwrap()).collect()
+    }
+
+    #[test]
+    fn mbox_is_split_on_from_lines() {
+        let mbox = "From a@example.com Mon Jan  6 10:00:00 2025\nSubject: A\n\nBody A\n\n\
+                    From b@example.com Tue Jan  7 10:00:00 2025\nSubject: B\n\nBody B\n";
+        assert_eq!(messages(mbox), ["Subject: A\n\nBody A\n\n", "Subject: B\n\nBody B\n"]);
+    }
+
+    #[test]
+    fn from_inside_a_paragraph_is_not_a_separator() {
+        let mbox = "From a@example.com Mon Jan  6 10:00:00 2025\nSubject: A\n\nHello\nFrom here on, it is body text\n";
+        assert_eq!(messages(mbox), ["Subject: A\n\nHello\nFrom here on, it is body text\n"]);
+    }
+
+    #[test]
+    fn escaped_from_lines_are_restored() {
+        let mbox = "From a@example.com Mon Jan  6 10:00:00 2025\nSubject: A\n\n>From the start\n>>From quoted\n>not a from line\n";
+        assert_eq!(messages(mbox), ["Subject: A\n\nFrom the start\n>From quoted\n>not a from line\n"]);
+    }
+
+    #[test]
+    fn text_before_the_first_message_and_empty_messages_are_ignored() {
+        let mbox = "garbage\n\nFrom a@example.com Mon Jan  6 10:00:00 2025\n\n\nFrom b@example.com Tue Jan  7 10:00:00 2025\nSubject: B\n";
+        assert_eq!(messages(mbox), ["Subject: B\n"]);
+        assert!(split_mbox(b"").is_empty());
+    }
+}
@@ -1,14 +1,40 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use rusqlite::{params, Connection, Result};
-use tauri::command;
-use log::info;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use tauri::{command, Manager, State};
+use log::{error, info};
 use env_logger;
 use serde::Serialize;
-use std::process::Command;
 use chrono::NaiveDate;
 use chrono::Local;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use vault::{resolve_file_reference, store_in_vault, vault_year};
+use attachments::{attach_stored_file, delete_record_attachments, replace_task_attachments};
+
+mod archive;
+mod attachments;
+mod dependencies;
+mod drafts;
+mod expiry;
+mod extraction;
+mod export;
+mod import;
+mod inbox;
+mod mail;
+mod recurrence;
+mod relink;
+mod reminders;
+mod retention;
+mod search;
+mod subtasks;
+mod suggestions;
+mod vault;
+mod versions;
 
 // ----------------------------------------- General models ---------------------------------------------
 
@@ -25,12 +51,29 @@ struct Invoice {
     description: Option<String>,
     status: String,
     payment_date: Option<String>,
+    reference: Option<String>,
+    is_draft: bool,
+}
+
+// Doublon potentiel détecté à l'ajout ou à l'import d'une facture
+#[derive(Serialize)]
+struct DuplicateCandidate {
+    invoice: Invoice,
+    reasons: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct AddInvoiceResult {
+    id: Option<i64>,
+    duplicates: Vec<DuplicateCandidate>,
 }
 
 #[derive(Serialize)]
 struct Creditor {
     id: i32,
     name: String,
+    iban: Option<String>,
+    email: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -56,6 +99,16 @@ struct AdminDocument {
     admin_doc_description: String,
     admin_doc_status: String,
     admin_doc_path: String,
+    lifecycle_status: String,
+    received_at: Option<String>,
+    to_process_at: Option<String>,
+    filed_at: Option<String>,
+    archived_at: Option<String>,
+    issue_date: Option<String>,
+    expiry_date: Option<String>,
+    renewal_lead_days: Option<i64>,
+    renewal_task_id: Option<i64>,
+    is_draft: bool,
 }
 
 #[derive(Serialize)]
@@ -82,6 +135,7 @@ struct Revenue {
     receipt_date: String,
     revenue_description: Option<String>,
     revenue_path: Option<String>,
+    is_draft: bool,
 }
 
 #[derive(Serialize)]
@@ -109,6 +163,15 @@ struct Task {
     creation_date: String,
     category: String,
     attachments: Option<String>,
+    updated_at: Option<String>,
+    completed_at: Option<String>,
+    parent_id: Option<i64>,
+    recurrence: Option<String>,
+    recurrence_start: Option<String>,
+    recurrence_mode: Option<String>,
+    next_occurrence_id: Option<i64>,
+    // Calculé : une des tâches dont elle dépend n'est pas terminée
+    blocked: bool,
 }
 
 #[derive(Serialize)]
@@ -127,6 +190,7 @@ struct TaskPriority {
 struct TaskStatus {
     id: i32,
     name: String,
+    is_done: bool,
 }
 
 // ----------------------------------------- Initialize database ----------------------------------
@@ -148,6 +212,9 @@ fn initialize_db() -> Result<()> {
         )",
         [],
     )?;
+    add_column_if_missing(&conn, "invoices", "reference", "TEXT")?;
+    add_column_if_missing(&conn, "invoices", "file_hash", "TEXT")?;
+    add_column_if_missing(&conn, "invoices", "is_draft", "INTEGER NOT NULL DEFAULT 0")?;
     // Creditors
     conn.execute(
         "CREATE TABLE IF NOT EXISTS creditors (
@@ -156,6 +223,19 @@ fn initialize_db() -> Result<()> {
         )",
         [],
     )?;
+    add_column_if_missing(&conn, "creditors", "iban", "TEXT")?;
+    add_column_if_missing(&conn, "creditors", "email", "TEXT")?;
+    // Règles d'extraction des champs de facture, par créancier
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS invoice_extraction_rules (
+            id INTEGER PRIMARY KEY,
+            creditor TEXT NOT NULL,
+            field TEXT NOT NULL,
+            pattern TEXT NOT NULL,
+            date_format TEXT
+        )",
+        [],
+    )?;
     // Concerns
     conn.execute(
         "CREATE TABLE IF NOT EXISTS concerns (
@@ -185,6 +265,7 @@ fn initialize_db() -> Result<()> {
         )",
         [],
     )?;
+    add_column_if_missing(&conn, "revenues", "is_draft", "INTEGER NOT NULL DEFAULT 0")?;
     // Revenues types
     conn.execute(
         "CREATE TABLE IF NOT EXISTS revenue_types (
@@ -213,6 +294,16 @@ fn initialize_db() -> Result<()> {
         )",
         [],
     )?;
+    add_column_if_missing(&conn, "admin_documents", "lifecycle_status", "TEXT NOT NULL DEFAULT 'received'")?;
+    add_column_if_missing(&conn, "admin_documents", "received_at", "TEXT")?;
+    add_column_if_missing(&conn, "admin_documents", "to_process_at", "TEXT")?;
+    add_column_if_missing(&conn, "admin_documents", "filed_at", "TEXT")?;
+    add_column_if_missing(&conn, "admin_documents", "archived_at", "TEXT")?;
+    add_column_if_missing(&conn, "admin_documents", "issue_date", "TEXT")?;
+    add_column_if_missing(&conn, "admin_documents", "expiry_date", "TEXT")?;
+    add_column_if_missing(&conn, "admin_documents", "renewal_lead_days", "INTEGER")?;
+    add_column_if_missing(&conn, "admin_documents", "renewal_task_id", "INTEGER")?;
+    add_column_if_missing(&conn, "admin_documents", "is_draft", "INTEGER NOT NULL DEFAULT 0")?;
     // Admin documents concerns
     conn.execute(
         "CREATE TABLE IF NOT EXISTS admin_documents_concerns (
@@ -244,6 +335,55 @@ fn initialize_db() -> Result<()> {
         )",
         [],
     )?;
+    add_column_if_missing(&conn, "tasks", "updated_at", "TEXT")?;
+    add_column_if_missing(&conn, "tasks", "completed_at", "TEXT")?;
+    add_column_if_missing(&conn, "tasks", "parent_id", "INTEGER")?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS task_checklist_items (
+            id INTEGER PRIMARY KEY,
+            task_id INTEGER NOT NULL,
+            label TEXT NOT NULL,
+            done INTEGER NOT NULL DEFAULT 0,
+            position INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_task_checklist_items_task ON task_checklist_items (task_id)",
+        [],
+    )?;
+    // Récurrence (RRULE normalisée) et lien vers l'occurrence suivante une fois créée
+    add_column_if_missing(&conn, "tasks", "recurrence", "TEXT")?;
+    add_column_if_missing(&conn, "tasks", "recurrence_start", "TEXT")?;
+    add_column_if_missing(&conn, "tasks", "recurrence_mode", "TEXT")?;
+    add_column_if_missing(&conn, "tasks", "next_occurrence_id", "INTEGER")?;
+    // Dépendances : task_id ne peut commencer qu'une fois depends_on_id terminée
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS task_dependencies (
+            id INTEGER PRIMARY KEY,
+            task_id INTEGER NOT NULL,
+            depends_on_id INTEGER NOT NULL,
+            UNIQUE (task_id, depends_on_id)
+        )",
+        [],
+    )?;
+    // Rappels : à une heure fixe (remind_at) ou un délai avant l'échéance (offset_minutes)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS task_reminders (
+            id INTEGER PRIMARY KEY,
+            task_id INTEGER NOT NULL,
+            remind_at TEXT,
+            offset_minutes INTEGER,
+            snoozed_until TEXT,
+            fired_at TEXT,
+            dismissed_at TEXT
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_task_reminders_task ON task_reminders (task_id)",
+        [],
+    )?;
     // Task categories
     conn.execute(
         "CREATE TABLE IF NOT EXISTS task_categories (
@@ -268,11 +408,245 @@ fn initialize_db() -> Result<()> {
         )",
         [],
     )?;
+    // Statut qui termine une tâche ; NULL jusqu'au rattrapage ci-dessous
+    add_column_if_missing(&conn, "task_statuses", "is_done", "INTEGER")?;
+    backfill_task_dates(&conn)?;
+    // Attachments (toutes les pièces jointes, quel que soit le type d'enregistrement)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS attachments (
+            id INTEGER PRIMARY KEY,
+            record_type TEXT NOT NULL,
+            record_id INTEGER NOT NULL,
+            file_reference TEXT NOT NULL,
+            original_name TEXT NOT NULL,
+            mime_type TEXT NOT NULL,
+            size INTEGER,
+            sha256 TEXT,
+            added_date TEXT NOT NULL,
+            position INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS attachments_record ON attachments (record_type, record_id)",
+        [],
+    )?;
+    add_column_if_missing(&conn, "attachments", "extracted_text", "TEXT")?;
+    add_column_if_missing(&conn, "attachments", "text_extracted_at", "TEXT")?;
+    add_column_if_missing(&conn, "attachments", "extraction_error", "TEXT")?;
+    attachments::migrate_legacy_attachments(&conn)?;
+    // Versions successives des fichiers des documents administratifs
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS admin_document_versions (
+            id INTEGER PRIMARY KEY,
+            admin_document_id INTEGER NOT NULL,
+            version INTEGER NOT NULL,
+            file_reference TEXT NOT NULL,
+            original_name TEXT NOT NULL,
+            note TEXT,
+            uploaded_date TEXT NOT NULL
+        )",
+        [],
+    )?;
+    versions::migrate_document_versions(&conn)?;
+    // Durées de conservation, par type d'enregistrement et catégorie (NULL = toutes)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS retention_rules (
+            id INTEGER PRIMARY KEY,
+            record_type TEXT NOT NULL,
+            category TEXT,
+            keep_years INTEGER NOT NULL,
+            anchor TEXT NOT NULL
+        )",
+        [],
+    )?;
+    // Réglages de l'application (clé / valeur)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT
+        )",
+        [],
+    )?;
+    // Sous-dossiers du dossier surveillé et type d'enregistrement créé pour leurs fichiers
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS inbox_rules (
+            id INTEGER PRIMARY KEY,
+            subfolder TEXT NOT NULL,
+            record_type TEXT NOT NULL,
+            category TEXT
+        )",
+        [],
+    )?;
+    // Empreintes des fichiers de l'inbox déjà importés, même s'ils n'ont pas pu quitter le dossier
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS inbox_processed_files (
+            sha256 TEXT PRIMARY KEY,
+            original_name TEXT NOT NULL,
+            processed_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    // Journal d'audit des opérations destructives
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY,
+            timestamp TEXT NOT NULL,
+            action TEXT NOT NULL,
+            record_type TEXT,
+            record_id INTEGER,
+            details TEXT
+        )",
+        [],
+    )?;
+    search::create_search_index(&conn)?;
+    Ok(())
+}
+
+// Ajoute une colonne aux bases créées avant son introduction
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|name| name.ok())
+        .any(|name| name == column);
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+    Ok(())
+}
+
+fn get_setting(conn: &Connection, key: &str) -> Result<Option<String>> {
+    let value = conn.query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| row.get(0));
+    match value {
+        Ok(value) => Ok(value),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn set_setting(conn: &Connection, key: &str, value: Option<&str>) -> Result<()> {
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )?;
     Ok(())
 }
 
 // ------------------------------------ Tasks functions -----------------------------------------
 
+const TASK_COLUMNS: &str = "id, title, description, status, priority, due_date, creation_date, category, attachments, updated_at, completed_at, parent_id, recurrence, recurrence_start, recurrence_mode, next_occurrence_id";
+
+// Statuts terminés par défaut : valeur initiale de is_done, et règle des statuts absents des réglages
+const DONE_TASK_STATUSES: &[&str] = &["completed", "done", "terminé", "termine", "fait"];
+
+// Les statuts sont libres, on les compare sans la casse ni les espaces autour
+fn normalize_status(status: &str) -> String {
+    status.trim().to_lowercase()
+}
+
+// Statuts qui terminent une tâche, d'après la case "Done" des réglages
+pub(crate) struct DoneStatuses(HashSet<String>);
+
+impl DoneStatuses {
+    fn load(conn: &Connection) -> Result<DoneStatuses> {
+        let mut done: HashSet<String> = DONE_TASK_STATUSES.iter().map(|s| s.to_string()).collect();
+        let mut stmt = conn.prepare("SELECT name, is_done FROM task_statuses WHERE is_done IS NOT NULL")?;
+        let statuses = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?)))?;
+        for status in statuses {
+            let (name, is_done) = status?;
+            if is_done {
+                done.insert(normalize_status(&name));
+            } else {
+                done.remove(&normalize_status(&name));
+            }
+        }
+        Ok(DoneStatuses(done))
+    }
+
+    pub(crate) fn contains(&self, status: &str) -> bool {
+        self.0.contains(&normalize_status(status))
+    }
+}
+
+pub(crate) fn done_statuses(conn: &Connection) -> Result<DoneStatuses, String> {
+    DoneStatuses::load(conn).map_err(|e| e.to_string())
+}
+
+fn is_done_status(conn: &Connection, status: &str) -> Result<bool, String> {
+    Ok(done_statuses(conn)?.contains(status))
+}
+
+fn task_from_row(row: &rusqlite::Row) -> Result<Task> {
+    Ok(Task {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        description: row.get(2)?,
+        status: row.get(3)?,
+        priority: row.get(4)?,
+        due_date: row.get(5)?,
+        creation_date: row.get(6)?,
+        category: row.get(7)?,
+        attachments: row.get(8)?,
+        updated_at: row.get(9)?,
+        completed_at: row.get(10)?,
+        parent_id: row.get(11)?,
+        recurrence: row.get(12)?,
+        recurrence_start: row.get(13)?,
+        recurrence_mode: row.get(14)?,
+        next_occurrence_id: row.get(15)?,
+        blocked: false,
+    })
+}
+
+fn get_task(conn: &Connection, id: i32) -> Result<Task, String> {
+    let mut task = conn.query_row(
+        &format!("SELECT {} FROM tasks WHERE id = ?1", TASK_COLUMNS),
+        params![id],
+        task_from_row,
+    ).optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Task {} not found", id))?;
+    dependencies::mark_blocked(conn, std::slice::from_mut(&mut task))?;
+    Ok(task)
+}
+
+// Date de fin d'une tâche après un changement de statut : conservée si elle était déjà terminée
+fn completion_date(conn: &Connection, previous: Option<&Task>, status: &str, current_date: &str) -> Result<Option<String>, String> {
+    let done = done_statuses(conn)?;
+    if !done.contains(status) {
+        return Ok(None);
+    }
+    Ok(match previous {
+        Some(task) if done.contains(&task.status) => task.completed_at.clone().or_else(|| Some(current_date.to_string())),
+        _ => Some(current_date.to_string()),
+    })
+}
+
+// Les tâches antérieures à ces colonnes n'ont que creation_date, qui était réécrite à chaque
+// modification : c'est donc la date de la dernière modification, et au mieux celle de la fin
+fn backfill_task_dates(conn: &Connection) -> Result<()> {
+    conn.execute("UPDATE tasks SET updated_at = creation_date WHERE updated_at IS NULL", [])?;
+
+    // Statuts d'avant la case "Done" (ou d'une ancienne sauvegarde) : cochée d'après la liste par défaut
+    let mut stmt = conn.prepare("SELECT id, name FROM task_statuses WHERE is_done IS NULL")?;
+    let statuses = stmt
+        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<Result<Vec<_>>>()?;
+    for (id, name) in statuses {
+        let is_done = DONE_TASK_STATUSES.contains(&normalize_status(&name).as_str());
+        conn.execute("UPDATE task_statuses SET is_done = ?1 WHERE id = ?2", params![is_done, id])?;
+    }
+
+    let done = DoneStatuses::load(conn)?;
+    let mut stmt = conn.prepare("SELECT DISTINCT status FROM tasks WHERE completed_at IS NULL")?;
+    let statuses = stmt.query_map([], |row| row.get::<_, String>(0))?.collect::<Result<Vec<_>>>()?;
+    for status in statuses.iter().filter(|status| done.contains(status)) {
+        conn.execute("UPDATE tasks SET completed_at = updated_at WHERE completed_at IS NULL AND status = ?1", params![status])?;
+    }
+    Ok(())
+}
+
 #[command]
 fn add_task(
     title: String,
@@ -281,70 +655,168 @@ fn add_task(
     priority: String,
     due_date: String,
     category: String,
-    attachments: Option<String>
+    attachments: Option<String>,
+    parent_id: Option<i32>
 ) -> Result<(), String> {
-    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    if let Some(parent_id) = parent_id {
+        get_task(&conn, parent_id)?;
+    }
     let current_date = chrono::Local::now().naive_local().to_string();
-    conn.execute(
-        "INSERT INTO tasks (title, description, status, priority, due_date, creation_date, category, attachments) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-        params![title, description, status, priority, due_date, current_date, category, attachments],
+    let completed_at = completion_date(&conn, None, &status, &current_date)?;
+    // Tâche et pièces jointes ensemble : un fichier illisible n'en laisse pas une à moitié créée
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO tasks (title, description, status, priority, due_date, creation_date, category, attachments, updated_at, completed_at, parent_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?6, ?9, ?10)",
+        params![title, description, status, priority, due_date, current_date, category, attachments, completed_at, parent_id],
     ).map_err(|e| e.to_string())?;
-    Ok(())
+    replace_task_attachments(&tx, tx.last_insert_rowid(), attachments.as_deref())?;
+    tx.commit().map_err(|e| e.to_string())
 }
 
+// Supprime aussi les sous-tâches, à toutes les profondeurs
 #[command]
 fn delete_task(id: i32) -> Result<(), String> {
-    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
-    conn.execute(
-        "DELETE FROM tasks WHERE id = ?1",
-        params![id],
-    ).map_err(|e| e.to_string())?;
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let mut ids = subtasks::descendant_ids(&conn, id as i64)?;
+    ids.push(id as i64);
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for id in ids {
+        tx.execute(
+            "DELETE FROM tasks WHERE id = ?1",
+            params![id],
+        ).map_err(|e| e.to_string())?;
+        tx.execute(
+            "DELETE FROM task_checklist_items WHERE task_id = ?1",
+            params![id],
+        ).map_err(|e| e.to_string())?;
+        tx.execute(
+            "DELETE FROM task_dependencies WHERE task_id = ?1 OR depends_on_id = ?1",
+            params![id],
+        ).map_err(|e| e.to_string())?;
+        reminders::delete_task_reminders(&tx, id)?;
+        delete_record_attachments(&tx, "tasks", id)?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
     Ok(())
 }
 
 #[command]
 fn get_tasks() -> Result<Vec<Task>, String> {
     let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
-    let mut stmt = conn.prepare("SELECT id, title, description, status, priority, due_date, creation_date, category, attachments FROM tasks").map_err(|e| e.to_string())?;
-    let task_iter = stmt.query_map([], |row| {
-        Ok(Task {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            description: row.get(2)?,
-            status: row.get(3)?,
-            priority: row.get(4)?,
-            due_date: row.get(5)?,
-            creation_date: row.get(6)?,
-            category: row.get(7)?,
-            attachments: row.get(8)?,
-        })
-    }).map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM tasks", TASK_COLUMNS)).map_err(|e| e.to_string())?;
+    let task_iter = stmt.query_map([], task_from_row).map_err(|e| e.to_string())?;
 
     let mut tasks = Vec::new();
     for task in task_iter {
         tasks.push(task.map_err(|e| e.to_string())?);
     }
+    dependencies::mark_blocked(&conn, &mut tasks)?;
     Ok(tasks)
 }
 
-#[command]
-fn update_task(
-    id: i32,
-    title: String,
-    description: String,
-    status: String,
-    priority: String,
-    due_date: String,
-    category: String,
-    attachments: Option<String>
-) -> Result<(), String> {
-    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+// Champs modifiables d'une tâche ; None laisse le champ inchangé
+#[derive(Default)]
+struct TaskChanges {
+    title: Option<String>,
+    description: Option<String>,
+    status: Option<String>,
+    priority: Option<String>,
+    due_date: Option<String>,
+    category: Option<String>,
+    // "" retire toutes les pièces jointes
+    attachments: Option<String>,
+}
+
+// creation_date n'est jamais modifiée ; updated_at et completed_at sont tenues à jour ici
+// À appeler dans une transaction : le changement de statut et la création de l'occurrence suivante vont ensemble
+fn apply_task_changes(conn: &Connection, id: i32, changes: TaskChanges) -> Result<Task, String> {
+    let previous = get_task(conn, id)?;
+    if changes.title.as_deref().is_some_and(|t| t.trim().is_empty()) {
+        return Err("The task title cannot be empty".to_string());
+    }
+    if changes.status.as_deref().is_some_and(|s| s.trim().is_empty()) {
+        return Err("The task status cannot be empty".to_string());
+    }
+    if changes.priority.as_deref().is_some_and(|p| p.trim().is_empty()) {
+        return Err("The task priority cannot be empty".to_string());
+    }
+
+    let status = changes.status.unwrap_or_else(|| previous.status.clone());
     let current_date = chrono::Local::now().naive_local().to_string();
+    let completed_at = completion_date(conn, Some(&previous), &status, &current_date)?;
+    let attachments = changes.attachments.as_ref().map(|a| Some(a.clone()).filter(|a| !a.trim().is_empty()));
+    let due_date = changes.due_date.unwrap_or_else(|| previous.due_date.clone());
     conn.execute(
-        "UPDATE tasks SET title = ?1, description = ?2, status = ?3, priority = ?4, due_date = ?5, creation_date = ?6, category = ?7, attachments = ?8 WHERE id = ?9",
-        params![title, description, status, priority, due_date, current_date, category, attachments, id],
+        "UPDATE tasks SET title = ?1, description = ?2, status = ?3, priority = ?4, due_date = ?5, category = ?6, attachments = ?7, updated_at = ?8, completed_at = ?9 WHERE id = ?10",
+        params![
+            changes.title.unwrap_or(previous.title),
+            changes.description.unwrap_or(previous.description),
+            status,
+            changes.priority.unwrap_or(previous.priority),
+            due_date,
+            changes.category.unwrap_or(previous.category),
+            attachments.clone().unwrap_or(previous.attachments),
+            current_date,
+            completed_at,
+            id
+        ],
     ).map_err(|e| e.to_string())?;
-    Ok(())
+    if let Some(attachments) = attachments {
+        replace_task_attachments(conn, id as i64, attachments.as_deref())?;
+    }
+    if due_date != previous.due_date {
+        reminders::reset_relative_reminders(conn, id as i64)?;
+    }
+    if !is_done_status(conn, &previous.status)? && is_done_status(conn, &status)? {
+        recurrence::create_next_occurrence(conn, id as i64)?;
+    }
+    get_task(conn, id)
+}
+
+// Mise à jour partielle : seuls les champs fournis sont modifiés
+#[command]
+fn update_task(
+    id: i32,
+    title: Option<String>,
+    description: Option<String>,
+    status: Option<String>,
+    priority: Option<String>,
+    due_date: Option<String>,
+    category: Option<String>,
+    attachments: Option<String>,
+    scheduler: State<'_, reminders::ReminderScheduler>
+) -> Result<Task, String> {
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let due_date_changed = due_date.is_some();
+    let task = apply_task_changes(&tx, id, TaskChanges { title, description, status, priority, due_date, category, attachments })?;
+    tx.commit().map_err(|e| e.to_string())?;
+    // Les rappels relatifs suivent la nouvelle échéance
+    if due_date_changed {
+        scheduler.wake()?;
+    }
+    Ok(task)
+}
+
+// Déplacement d'une tâche sur le tableau (Kanban) sans renvoyer toute la tâche
+#[command]
+fn set_task_status(id: i32, status: String) -> Result<Task, String> {
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let task = apply_task_changes(&tx, id, TaskChanges { status: Some(status), ..Default::default() })?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(task)
+}
+
+#[command]
+fn set_task_priority(id: i32, priority: String) -> Result<Task, String> {
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let task = apply_task_changes(&tx, id, TaskChanges { priority: Some(priority), ..Default::default() })?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(task)
 }
 
 // Fonctions pour gérer les catégories des tâches
@@ -427,23 +899,42 @@ fn delete_task_priority(id: i32) -> Result<(), String> {
 
 // Fonctions pour gérer les statuts des tâches
 #[command]
-fn add_task_status(name: String) -> Result<(), String> {
+fn add_task_status(name: String, is_done: Option<bool>) -> Result<(), String> {
     let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let is_done = is_done.unwrap_or_else(|| DONE_TASK_STATUSES.contains(&normalize_status(&name).as_str()));
     conn.execute(
-        "INSERT INTO task_statuses (name) VALUES (?1)",
-        params![name],
+        "INSERT INTO task_statuses (name, is_done) VALUES (?1, ?2)",
+        params![name, is_done],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Coche ou décoche "Done" : les tâches déjà dans ce statut gardent leur date de fin
+#[command]
+fn set_task_status_done(id: i32, is_done: bool) -> Result<(), String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let updated = conn.execute(
+        "UPDATE task_statuses SET is_done = ?1 WHERE id = ?2",
+        params![is_done, id],
     ).map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Task status {} not found", id));
+    }
     Ok(())
 }
 
 #[command]
 fn get_task_statuses() -> Result<Vec<TaskStatus>, String> {
     let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    // Un statut créé par un import CSV n'a pas encore de case cochée : la règle par défaut s'applique
+    let done = done_statuses(&conn)?;
     let mut stmt = conn.prepare("SELECT id, name FROM task_statuses").map_err(|e| e.to_string())?;
     let task_status_iter = stmt.query_map([], |row| {
+        let name: String = row.get(1)?;
         Ok(TaskStatus {
             id: row.get(0)?,
-            name: row.get(1)?,
+            is_done: done.contains(&name),
+            name,
         })
     }).map_err(|e| e.to_string())?;
 
@@ -466,50 +957,173 @@ fn delete_task_status(id: i32) -> Result<(), String> {
 
 
 // ------------------------------------ Admin_Docs functions -----------------------------------------
+const ADMIN_DOC_COLUMNS: &str = "id, admin_doc_concern, admin_doc_category, admin_doc_description, admin_doc_status, admin_doc_path, lifecycle_status, received_at, to_process_at, filed_at, archived_at, issue_date, expiry_date, renewal_lead_days, renewal_task_id, is_draft";
+
+// Étapes du cycle de vie d'un document, dans l'ordre, avec la colonne qui date le passage à l'étape
+const ADMIN_DOC_LIFECYCLE: &[(&str, &str)] = &[
+    ("received", "received_at"),
+    ("to_process", "to_process_at"),
+    ("filed", "filed_at"),
+    ("archived", "archived_at"),
+];
+
+fn admin_doc_from_row(row: &rusqlite::Row) -> Result<AdminDocument> {
+    Ok(AdminDocument {
+        id: row.get(0)?,
+        admin_doc_concern: row.get(1)?,
+        admin_doc_category: row.get(2)?,
+        admin_doc_description: row.get(3)?,
+        admin_doc_status: row.get(4)?,
+        admin_doc_path: row.get(5)?,
+        lifecycle_status: row.get(6)?,
+        received_at: row.get(7)?,
+        to_process_at: row.get(8)?,
+        filed_at: row.get(9)?,
+        archived_at: row.get(10)?,
+        issue_date: row.get(11)?,
+        expiry_date: row.get(12)?,
+        renewal_lead_days: row.get(13)?,
+        renewal_task_id: row.get(14)?,
+        is_draft: row.get(15)?,
+    })
+}
+
+fn lifecycle_stage(lifecycle_status: &str) -> Result<usize, String> {
+    ADMIN_DOC_LIFECYCLE
+        .iter()
+        .position(|(status, _)| *status == lifecycle_status)
+        .ok_or_else(|| format!("Unknown lifecycle status: {}", lifecycle_status))
+}
+
+fn check_document_dates(issue_date: Option<&str>, expiry_date: Option<&str>, renewal_lead_days: Option<i64>) -> Result<(), String> {
+    let parse = |date: Option<&str>| {
+        date.map(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").map_err(|_| format!("Invalid date: {}", d)))
+            .transpose()
+    };
+    if let (Some(issue), Some(expiry)) = (parse(issue_date)?, parse(expiry_date)?) {
+        if expiry < issue {
+            return Err("The expiry date is before the issue date".to_string());
+        }
+    }
+    if renewal_lead_days.is_some_and(|days| !(0..=expiry::MAX_RENEWAL_LEAD_DAYS).contains(&days)) {
+        return Err(format!("The renewal lead time must be between 0 and {} days", expiry::MAX_RENEWAL_LEAD_DAYS));
+    }
+    Ok(())
+}
+
+fn get_admin_doc(conn: &Connection, id: i32) -> Result<AdminDocument, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM admin_documents WHERE id = ?1", ADMIN_DOC_COLUMNS),
+        params![id],
+        admin_doc_from_row,
+    ).map_err(|e| e.to_string())
+}
+
+fn query_admin_docs(conn: &Connection, filter: &str, params: &[&dyn rusqlite::ToSql]) -> Result<Vec<AdminDocument>, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM admin_documents {} ORDER BY id", ADMIN_DOC_COLUMNS, filter))
+        .map_err(|e| e.to_string())?;
+    let admin_doc_iter = stmt.query_map(params, admin_doc_from_row).map_err(|e| e.to_string())?;
+
+    let mut admin_docs = Vec::new();
+    for admin_doc in admin_doc_iter {
+        admin_docs.push(admin_doc.map_err(|e| e.to_string())?);
+    }
+    Ok(admin_docs)
+}
+
 #[command]
 fn add_admin_doc(
     admin_doc_concern: String,
     admin_doc_category: String,
     admin_doc_description: String,
     admin_doc_status: String,
-    admin_doc_path: String
+    admin_doc_path: String,
+    issue_date: Option<String>,
+    expiry_date: Option<String>,
+    renewal_lead_days: Option<i64>,
+    move_file: Option<bool>
 ) -> Result<(), String> {
+    check_document_dates(issue_date.as_deref(), expiry_date.as_deref(), renewal_lead_days)?;
     // Ouvrir une connexion à la base de données SQLite
     let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
 
+    let reference = store_in_vault(&admin_doc_path, "admin_documents", vault_year(None), move_file.unwrap_or(false))?;
+    let current_date = chrono::Local::now().naive_local().to_string();
+
     // Exécuter une commande SQL pour insérer les données dans la table 
     conn.execute(
-        "INSERT INTO admin_documents (admin_doc_concern, admin_doc_category, admin_doc_description, admin_doc_status, admin_doc_path) VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![admin_doc_concern, admin_doc_category, admin_doc_description, admin_doc_status, admin_doc_path],
+        "INSERT INTO admin_documents (admin_doc_concern, admin_doc_category, admin_doc_description, admin_doc_status, admin_doc_path, lifecycle_status, received_at, issue_date, expiry_date, renewal_lead_days) VALUES (?1, ?2, ?3, ?4, ?5, 'received', ?6, ?7, ?8, ?9)",
+        params![admin_doc_concern, admin_doc_category, admin_doc_description, admin_doc_status, reference, current_date, issue_date, expiry_date, renewal_lead_days],
     ).map_err(|e| e.to_string())?;
+    let id = conn.last_insert_rowid();
+    attach_stored_file(&conn, "admin_documents", id, &reference, &admin_doc_path)?;
+    versions::insert_version(&conn, id, &reference, &vault::file_name_of(&admin_doc_path), None)?;
 
     Ok(())
 }
 
+// Les brouillons ne sont listés qu'à la demande : ils se relisent depuis l'inbox
 #[command]
-fn get_admin_docs() -> Result<Vec<AdminDocument>, String> {
-    // Ouvrir une connexion à la base de données SQLite
+fn get_admin_docs(include_drafts: Option<bool>) -> Result<Vec<AdminDocument>, String> {
     let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let filter = if include_drafts.unwrap_or(false) { "" } else { "WHERE is_draft = 0" };
+    query_admin_docs(&conn, filter, &[])
+}
 
-    // Exécuter une commande SQL pour récupérer les données de la table
-    let mut stmt = conn.prepare("SELECT * FROM admin_documents").map_err(|e| e.to_string())?;
-    let admin_doc_iter = stmt.query_map([], |row| {
-        Ok(AdminDocument {
-            id: row.get(0)?,
-            admin_doc_concern: row.get(1)?,
-            admin_doc_category: row.get(2)?,
-            admin_doc_description: row.get(3)?,
-            admin_doc_status: row.get(4)?,
-            admin_doc_path: row.get(5)?,
-        })
-    }).map_err(|e| e.to_string())?;
+#[command]
+fn get_admin_docs_by_lifecycle(lifecycle_status: String, include_drafts: Option<bool>) -> Result<Vec<AdminDocument>, String> {
+    lifecycle_stage(&lifecycle_status)?;
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let filter = if include_drafts.unwrap_or(false) { "WHERE lifecycle_status = ?1" } else { "WHERE lifecycle_status = ?1 AND is_draft = 0" };
+    query_admin_docs(&conn, filter, &[&lifecycle_status])
+}
 
-    let mut admin_docs = Vec::new();
-    for admin_doc in admin_doc_iter {
-        admin_docs.push(admin_doc.map_err(|e| e.to_string())?);
+// Les fichiers se gèrent avec les commandes des pièces jointes
+#[command]
+fn update_admin_doc(
+    id: i32,
+    admin_doc_concern: String,
+    admin_doc_category: String,
+    admin_doc_description: String,
+    admin_doc_status: String,
+    issue_date: Option<String>,
+    expiry_date: Option<String>,
+    renewal_lead_days: Option<i64>
+) -> Result<AdminDocument, String> {
+    check_document_dates(issue_date.as_deref(), expiry_date.as_deref(), renewal_lead_days)?;
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let previous = get_admin_doc(&conn, id)?;
+    // Nouvelle échéance (document renouvelé) : une nouvelle tâche de renouvellement pourra être créée
+    let renewal_task_id = if previous.expiry_date == expiry_date { previous.renewal_task_id } else { None };
+
+    conn.execute(
+        "UPDATE admin_documents SET admin_doc_concern = ?1, admin_doc_category = ?2, admin_doc_description = ?3, admin_doc_status = ?4, issue_date = ?5, expiry_date = ?6, renewal_lead_days = ?7, renewal_task_id = ?8 WHERE id = ?9",
+        params![admin_doc_concern, admin_doc_category, admin_doc_description, admin_doc_status, issue_date, expiry_date, renewal_lead_days, renewal_task_id, id],
+    ).map_err(|e| e.to_string())?;
+    get_admin_doc(&conn, id)
+}
+
+// Fait passer un document à une étape du cycle de vie ; revenir en arrière efface les dates des étapes suivantes
+#[command]
+fn set_admin_doc_lifecycle(id: i32, lifecycle_status: String) -> Result<AdminDocument, String> {
+    let stage = lifecycle_stage(&lifecycle_status)?;
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let admin_doc = get_admin_doc(&conn, id)?;
+    if admin_doc.lifecycle_status == lifecycle_status {
+        return Ok(admin_doc);
     }
 
-    Ok(admin_docs)
+    let current_date = chrono::Local::now().naive_local().to_string();
+    let mut assignments = vec![format!("lifecycle_status = ?1, {} = ?2", ADMIN_DOC_LIFECYCLE[stage].1)];
+    for (_, column) in &ADMIN_DOC_LIFECYCLE[stage + 1..] {
+        assignments.push(format!("{} = NULL", column));
+    }
+    conn.execute(
+        &format!("UPDATE admin_documents SET {} WHERE id = ?3", assignments.join(", ")),
+        params![lifecycle_status, current_date, id],
+    ).map_err(|e| e.to_string())?;
+    get_admin_doc(&conn, id)
 }
 
 #[command]
@@ -519,6 +1133,8 @@ fn delete_admin_doc(id: i32) -> Result<(), String> {
         "DELETE FROM admin_documents WHERE id = ?1",
         params![id],
     ).map_err(|e| e.to_string())?;
+    delete_record_attachments(&conn, "admin_documents", id as i64)?;
+    versions::delete_document_versions(&conn, id as i64)?;
     Ok(())
 }
 
@@ -602,6 +1218,90 @@ fn delete_admin_document_category(id: i32) -> Result<(), String> {
 
 
 // ------------------------------------ Invoices functions -----------------------------------------
+const INVOICE_COLUMNS: &str = "id, creditor, concern, category, amount, due_date, path, description, status, payment_date, reference, is_draft";
+
+// Tolérances utilisées pour repérer une facture saisie deux fois
+const DUPLICATE_AMOUNT_TOLERANCE: f64 = 0.01;
+const DUPLICATE_DUE_DATE_TOLERANCE_DAYS: i64 = 7;
+
+fn invoice_from_row(row: &rusqlite::Row) -> Result<Invoice> {
+    Ok(Invoice {
+        id: row.get(0)?,
+        creditor: row.get(1)?,
+        concern: row.get(2)?,
+        category: row.get(3)?,
+        amount: row.get(4)?,
+        due_date: row.get(5)?,
+        path: row.get(6)?,
+        description: row.get(7)?,
+        status: row.get(8)?,
+        payment_date: row.get(9)?,
+        reference: row.get(10)?,
+        is_draft: row.get(11)?,
+    })
+}
+
+// Calcule le hash SHA-256 (hexadécimal) du contenu d'un fichier
+fn hash_file<P: AsRef<Path>>(path: P) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher).map_err(|e| e.to_string())?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// Recherche les factures existantes qui ressemblent à celle qu'on s'apprête à enregistrer
+fn find_duplicate_invoices(
+    conn: &Connection,
+    creditor: &str,
+    amount: f64,
+    due_date: &str,
+    reference: Option<&str>,
+    file_hash: Option<&str>
+) -> Result<Vec<DuplicateCandidate>, String> {
+    let due_date = NaiveDate::parse_from_str(due_date, "%Y-%m-%d").ok();
+    let reference = reference.map(str::trim).filter(|r| !r.is_empty());
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, file_hash FROM invoices WHERE creditor = ?1 OR (?2 IS NOT NULL AND reference = ?2) OR (?3 IS NOT NULL AND file_hash = ?3)",
+        INVOICE_COLUMNS
+    )).map_err(|e| e.to_string())?;
+    let candidate_iter = stmt.query_map(params![creditor, reference, file_hash], |row| {
+        Ok((invoice_from_row(row)?, row.get::<_, Option<String>>(12)?))
+    }).map_err(|e| e.to_string())?;
+
+    let mut duplicates = Vec::new();
+    for candidate in candidate_iter {
+        let (invoice, existing_hash) = candidate.map_err(|e| e.to_string())?;
+        let mut reasons = Vec::new();
+
+        // Un créancier vide (brouillon non reconnu) ne permet pas de rapprocher deux factures
+        if !creditor.trim().is_empty() && invoice.creditor == creditor && (invoice.amount - amount).abs() < DUPLICATE_AMOUNT_TOLERANCE {
+            let existing_due_date = NaiveDate::parse_from_str(&invoice.due_date, "%Y-%m-%d").ok();
+            if let (Some(a), Some(b)) = (due_date, existing_due_date) {
+                if (a - b).num_days().abs() <= DUPLICATE_DUE_DATE_TOLERANCE_DAYS {
+                    reasons.push("same_creditor_amount_due_date".to_string());
+                }
+            }
+        }
+        if reference.is_some() && invoice.reference.as_deref().map(str::trim) == reference {
+            reasons.push("same_reference".to_string());
+        }
+        if file_hash.is_some() && existing_hash.as_deref() == file_hash {
+            reasons.push("same_file".to_string());
+        }
+
+        if !reasons.is_empty() {
+            duplicates.push(DuplicateCandidate { invoice, reasons });
+        }
+    }
+    Ok(duplicates)
+}
+
+// Le même fichier qu'une facture existante : doublon certain, contrairement aux autres ressemblances
+fn is_same_file_duplicate(duplicates: &[DuplicateCandidate]) -> bool {
+    duplicates.iter().any(|d| d.reasons.iter().any(|r| r == "same_file"))
+}
+
 #[command]
 fn add_invoice(
     creditor: String, 
@@ -612,8 +1312,11 @@ fn add_invoice(
     path: Option<String>, 
     description: Option<String>, 
     status: Option<String>, 
-    payment_date: Option<String>
-) -> Result<(), String> {
+    payment_date: Option<String>,
+    reference: Option<String>,
+    force: Option<bool>,
+    move_file: Option<bool>
+) -> Result<AddInvoiceResult, String> {
     info!("Received arguments:");
     info!("creditor: {}", creditor);
     info!("concern: {}", concern);
@@ -624,35 +1327,44 @@ fn add_invoice(
     info!("description: {:?}", description);
     info!("status: {:?}", status);
     info!("payment_date: {:?}", payment_date);
+    info!("reference: {:?}", reference);
 
     let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+
+    let path = path.filter(|p| !p.is_empty());
+    let file_hash = match path.as_deref() {
+        Some(p) => Some(hash_file(resolve_file_reference(p)?)?),
+        None => None,
+    };
+
+    // Sans confirmation explicite, on renvoie les doublons probables au lieu d'enregistrer
+    if !force.unwrap_or(false) {
+        let duplicates = find_duplicate_invoices(&conn, &creditor, amount, &due_date, reference.as_deref(), file_hash.as_deref())?;
+        if !duplicates.is_empty() {
+            return Ok(AddInvoiceResult { id: None, duplicates });
+        }
+    }
+
+    // Le fichier est rangé dans le coffre, la facture garde la référence
+    let file_reference = match path.as_deref() {
+        Some(p) => Some(store_in_vault(p, "invoices", vault_year(Some(&due_date)), move_file.unwrap_or(false))?),
+        None => None,
+    };
+
     conn.execute(
-        "INSERT INTO invoices (creditor, concern, category, amount, due_date, path, description, status, payment_date) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-        params![creditor, concern, category, amount, due_date, path, description, status, payment_date],
+        "INSERT INTO invoices (creditor, concern, category, amount, due_date, path, description, status, payment_date, reference, file_hash) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![creditor, concern, category, amount, due_date, file_reference, description, status, payment_date, reference, file_hash],
     ).map_err(|e| e.to_string())?;
-    Ok(())
+    let id = conn.last_insert_rowid();
+    if let (Some(file_reference), Some(path)) = (file_reference, path) {
+        attach_stored_file(&conn, "invoices", id, &file_reference, &path)?;
+    }
+    Ok(AddInvoiceResult { id: Some(id), duplicates: Vec::new() })
 }
 
-#[command]
-fn get_invoices() -> Result<Vec<Invoice>, String> {
-    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
-    
-    // Récupérer toutes les factures
-    let mut stmt = conn.prepare("SELECT id, creditor, concern, category, amount, due_date, path, description, status, payment_date FROM invoices").map_err(|e| e.to_string())?;
-    let invoice_iter = stmt.query_map([], |row| {
-        Ok(Invoice {
-            id: row.get(0)?,
-            creditor: row.get(1)?,
-            concern: row.get(2)?,
-            category: row.get(3)?,
-            amount: row.get(4)?,
-            due_date: row.get(5)?,
-            path: row.get(6)?,
-            description: row.get(7)?,
-            status: row.get(8)?,
-            payment_date: row.get(9)?,
-        })
-    }).map_err(|e| e.to_string())?;
+fn query_invoices(conn: &Connection, filter: &str) -> Result<Vec<Invoice>, String> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM invoices {}", INVOICE_COLUMNS, filter)).map_err(|e| e.to_string())?;
+    let invoice_iter = stmt.query_map([], invoice_from_row).map_err(|e| e.to_string())?;
 
     let mut invoices = Vec::new();
     let current_date = Local::now().naive_local().date();
@@ -673,6 +1385,45 @@ fn get_invoices() -> Result<Vec<Invoice>, String> {
     Ok(invoices)
 }
 
+// Les brouillons sont exclus par défaut : ils ne comptent pas dans les totaux tant qu'ils ne sont pas approuvés
+#[command]
+fn get_invoices(include_drafts: Option<bool>) -> Result<Vec<Invoice>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    query_invoices(&conn, if include_drafts.unwrap_or(false) { "" } else { "WHERE is_draft = 0" })
+}
+
+fn get_invoice(conn: &Connection, id: i32) -> Result<Invoice, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM invoices WHERE id = ?1", INVOICE_COLUMNS),
+        params![id],
+        invoice_from_row,
+    ).map_err(|e| e.to_string())
+}
+
+// Corrige les champs d'une facture (un brouillon avant approbation, par exemple) ; les fichiers se gèrent avec les commandes des pièces jointes
+#[command]
+fn update_invoice(
+    id: i32,
+    creditor: String,
+    concern: String,
+    category: String,
+    amount: f64,
+    due_date: String,
+    description: Option<String>,
+    reference: Option<String>
+) -> Result<Invoice, String> {
+    NaiveDate::parse_from_str(&due_date, "%Y-%m-%d").map_err(|_| format!("Invalid due date: {}", due_date))?;
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    get_invoice(&conn, id)?;
+
+    let reference = reference.map(|r| r.trim().to_string()).filter(|r| !r.is_empty());
+    conn.execute(
+        "UPDATE invoices SET creditor = ?1, concern = ?2, category = ?3, amount = ?4, due_date = ?5, description = ?6, reference = ?7 WHERE id = ?8",
+        params![creditor, concern, category, amount, due_date, description, reference, id],
+    ).map_err(|e| e.to_string())?;
+    get_invoice(&conn, id)
+}
+
 #[command]
 fn delete_invoice(id: i32) -> Result<(), String> {
     let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
@@ -680,6 +1431,7 @@ fn delete_invoice(id: i32) -> Result<(), String> {
         "DELETE FROM invoices WHERE id = ?1",
         params![id],
     ).map_err(|e| e.to_string())?;
+    delete_record_attachments(&conn, "invoices", id as i64)?;
     Ok(())
 }
 
@@ -707,35 +1459,48 @@ fn add_revenue(
     revenue_amount: f64,
     receipt_date: String,
     revenue_description: Option<String>,
-    revenue_path: Option<String>
+    revenue_path: Option<String>,
+    move_file: Option<bool>
 ) -> Result<(), String> {
     // Ouvrir une connexion à la base de données SQLite
     let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
 
+    let revenue_path = revenue_path.filter(|p| !p.is_empty());
+    let reference = match revenue_path.as_deref() {
+        Some(p) => Some(store_in_vault(p, "revenues", vault_year(Some(&receipt_date)), move_file.unwrap_or(false))?),
+        None => None,
+    };
+
     // Exécuter une commande SQL pour insérer les données dans la table 'revenues'
     conn.execute(
         "INSERT INTO revenues (source, revenue_type, revenue_amount, receipt_date, revenue_description, revenue_path) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        params![source, revenue_type, revenue_amount, receipt_date, revenue_description, revenue_path],
+        params![source, revenue_type, revenue_amount, receipt_date, revenue_description, reference],
     ).map_err(|e| e.to_string())?;
+    if let (Some(reference), Some(path)) = (reference, revenue_path) {
+        attach_stored_file(&conn, "revenues", conn.last_insert_rowid(), &reference, &path)?;
+    }
 
     Ok(())
 }
 
-#[command]
-fn get_revenues() -> Result<Vec<Revenue>, String> {
-    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
-    let mut stmt = conn.prepare("SELECT id, source, revenue_type, revenue_amount, receipt_date, revenue_description, revenue_path FROM revenues").map_err(|e| e.to_string())?;
-    let revenue_iter = stmt.query_map([], |row| {
-        Ok(Revenue {
-            id: row.get(0)?,
-            source: row.get(1)?,
-            revenue_type: row.get(2)?,
-            revenue_amount: row.get(3)?,
-            receipt_date: row.get(4)?,
-            revenue_description: row.get(5)?,
-            revenue_path: row.get(6)?,
-        })
-    }).map_err(|e| e.to_string())?;
+const REVENUE_COLUMNS: &str = "id, source, revenue_type, revenue_amount, receipt_date, revenue_description, revenue_path, is_draft";
+
+fn revenue_from_row(row: &rusqlite::Row) -> Result<Revenue> {
+    Ok(Revenue {
+        id: row.get(0)?,
+        source: row.get(1)?,
+        revenue_type: row.get(2)?,
+        revenue_amount: row.get(3)?,
+        receipt_date: row.get(4)?,
+        revenue_description: row.get(5)?,
+        revenue_path: row.get(6)?,
+        is_draft: row.get(7)?,
+    })
+}
+
+fn query_revenues(conn: &Connection, filter: &str) -> Result<Vec<Revenue>, String> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM revenues {}", REVENUE_COLUMNS, filter)).map_err(|e| e.to_string())?;
+    let revenue_iter = stmt.query_map([], revenue_from_row).map_err(|e| e.to_string())?;
 
     let mut revenues = Vec::new();
     for revenue in revenue_iter {
@@ -744,6 +1509,12 @@ fn get_revenues() -> Result<Vec<Revenue>, String> {
     Ok(revenues)
 }
 
+#[command]
+fn get_revenues(include_drafts: Option<bool>) -> Result<Vec<Revenue>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    query_revenues(&conn, if include_drafts.unwrap_or(false) { "" } else { "WHERE is_draft = 0" })
+}
+
 #[command]
 fn delete_revenue(id: i32) -> Result<(), String> {
     let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
@@ -751,6 +1522,7 @@ fn delete_revenue(id: i32) -> Result<(), String> {
         "DELETE FROM revenues WHERE id = ?1",
         params![id],
     ).map_err(|e| e.to_string())?;
+    delete_record_attachments(&conn, "revenues", id as i64)?;
     Ok(())
 }
 
@@ -843,14 +1615,52 @@ fn add_creditor(name: String) -> Result<(), String> {
     Ok(())
 }
 
+// L'IBAN d'un créancier permet de le reconnaître sur ses factures
+#[command]
+fn set_creditor_iban(id: i32, iban: Option<String>) -> Result<(), String> {
+    let iban = iban.map(|i| suggestions::normalize_iban(&i)).filter(|i| !i.is_empty());
+    if let Some(iban) = &iban {
+        if !suggestions::is_valid_iban(iban) {
+            return Err(format!("Invalid IBAN: {}", iban));
+        }
+    }
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE creditors SET iban = ?1 WHERE id = ?2",
+        params![iban, id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Adresse ("factures@edf.fr") ou domaine ("edf.fr") d'expédition des e-mails d'un créancier
+#[command]
+fn set_creditor_email(id: i32, email: Option<String>) -> Result<(), String> {
+    let email = email
+        .map(|e| e.trim().trim_start_matches('@').to_lowercase())
+        .filter(|e| !e.is_empty());
+    if let Some(email) = &email {
+        if email.contains(char::is_whitespace) || !email.contains('.') {
+            return Err(format!("Invalid e-mail address or domain: {}", email));
+        }
+    }
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE creditors SET email = ?1 WHERE id = ?2",
+        params![email, id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
 #[command]
 fn get_creditors() -> Result<Vec<Creditor>, String> {
     let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
-    let mut stmt = conn.prepare("SELECT id, name FROM creditors").map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare("SELECT id, name, iban, email FROM creditors").map_err(|e| e.to_string())?;
     let creditor_iter = stmt.query_map([], |row| {
         Ok(Creditor {
             id: row.get(0)?,
             name: row.get(1)?,
+            iban: row.get(2)?,
+            email: row.get(3)?,
         })
     }).map_err(|e| e.to_string())?;
 
@@ -949,50 +1759,35 @@ fn delete_category(id: i32) -> Result<(), String> {
     Ok(())
 }
 
-// ------------------------------------ Utils functions -----------------------------------------
-#[command]
-fn open_file(path: String) -> Result<(), String> {
-    #[cfg(target_os = "windows")]
-    {
-        Command::new("explorer")
-            .arg(path)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
-
-    #[cfg(target_os = "macos")]
-    {
-        Command::new("open")
-            .arg(path)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
-
-    #[cfg(target_os = "linux")]
-    {
-        Command::new("xdg-open")
-            .arg(path)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
-
-    Ok(())
-}
-
 fn main() {
     env_logger::init();
 
     // Initialize the database
     initialize_db().expect("Failed to initialize the database");
+    if let Err(e) = expiry::create_renewal_tasks() {
+        error!("Failed to create renewal tasks: {}", e);
+    }
+    if let Err(e) = recurrence::create_scheduled_occurrences() {
+        error!("Failed to create recurring tasks: {}", e);
+    }
 
     tauri::Builder::default()
+        .setup(|app| {
+            app.manage(extraction::start(app.handle()));
+            app.manage(inbox::start(app.handle()));
+            app.manage(reminders::start(app.handle()));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             add_invoice,
             get_invoices,
+            update_invoice,
             delete_invoice,
             pay_invoice,
             add_creditor,
             get_creditors,
+            set_creditor_iban,
+            set_creditor_email,
             delete_creditor,
             add_category,
             get_categories,
@@ -1000,7 +1795,6 @@ fn main() {
             add_concern,
             get_concerns,
             delete_concern,
-            open_file,
             add_revenue,
             get_revenues,
             delete_revenue,
@@ -1012,6 +1806,10 @@ fn main() {
             delete_revenue_type,
             add_admin_doc,
             get_admin_docs,
+            get_admin_docs_by_lifecycle,
+            update_admin_doc,
+            set_admin_doc_lifecycle,
+            expiry::get_expiring_admin_docs,
             delete_admin_doc,
             add_admin_document_category,
             get_admin_document_categories,
@@ -1023,6 +1821,30 @@ fn main() {
             get_tasks,
             delete_task,
             update_task,
+            set_task_status,
+            set_task_priority,
+            subtasks::set_task_parent,
+            subtasks::get_task_tree,
+            subtasks::get_task_tree_flat,
+            subtasks::get_checklist,
+            subtasks::add_checklist_item,
+            subtasks::update_checklist_item,
+            subtasks::delete_checklist_item,
+            subtasks::reorder_checklist_items,
+            dependencies::add_task_dependency,
+            dependencies::remove_task_dependency,
+            dependencies::get_task_dependencies,
+            dependencies::complete_task,
+            recurrence::set_task_recurrence,
+            recurrence::preview_recurrence,
+            recurrence::preview_task_occurrences,
+            recurrence::generate_recurring_tasks,
+            reminders::add_task_reminder,
+            reminders::get_task_reminders,
+            reminders::get_fired_reminders,
+            reminders::delete_task_reminder,
+            reminders::snooze_reminder,
+            reminders::dismiss_reminder,
             add_task_category,
             get_task_categories,
             delete_task_category,
@@ -1031,7 +1853,45 @@ fn main() {
             delete_task_priority,
             add_task_status,
             get_task_statuses,
-            delete_task_status
+            set_task_status_done,
+            delete_task_status,
+            import::import_csv,
+            export::export_records,
+            archive::export_database,
+            archive::import_database,
+            archive::export_portable_archive,
+            archive::import_portable_archive,
+            relink::scan_broken_attachments,
+            relink::find_relink_candidates,
+            relink::relink_attachments,
+            attachments::add_attachment,
+            attachments::get_attachments,
+            attachments::remove_attachment,
+            attachments::reorder_attachments,
+            attachments::open_attachment,
+            search::search,
+            extraction::extract_attachment_texts,
+            suggestions::suggest_invoice_fields,
+            suggestions::add_extraction_rule,
+            suggestions::get_extraction_rules,
+            suggestions::delete_extraction_rule,
+            versions::add_admin_doc_version,
+            versions::get_admin_doc_versions,
+            versions::restore_admin_doc_version,
+            retention::add_retention_rule,
+            retention::get_retention_rules,
+            retention::delete_retention_rule,
+            retention::get_retention_candidates,
+            retention::purge_records,
+            drafts::get_inbox,
+            drafts::approve_draft,
+            drafts::reject_draft,
+            inbox::get_inbox_settings,
+            inbox::set_inbox_settings,
+            inbox::add_inbox_rule,
+            inbox::delete_inbox_rule,
+            inbox::scan_inbox_now,
+            mail::import_emails
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
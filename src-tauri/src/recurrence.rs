@@ -0,0 +1,563 @@
+// ------------------------------------ Recurring tasks -----------------------------------------
+//
+// Relevés de compteurs, déclarations annuelles : une tâche peut porter une règle de récurrence
+// (sous-ensemble des RRULE de la RFC 5545 : FREQ, INTERVAL, BYDAY, UNTIL, COUNT). L'occurrence
+// suivante est créée quand la tâche est terminée, ou dès son échéance en mode "schedule".
+
+use std::collections::HashSet;
+use std::fmt;
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::command;
+
+use crate::reminders::copy_relative_reminders;
+use crate::{get_task, is_done_status, Task};
+
+const OCCURRENCE_STATUS: &str = "Pending";
+// Mode par défaut : l'occurrence suivante n'apparaît qu'une fois la tâche terminée
+const MODE_COMPLETION: &str = "completion";
+const MODE_SCHEDULE: &str = "schedule";
+const DEFAULT_PREVIEW_LIMIT: usize = 10;
+// Une règle qui ne tombe jamais (31 février...) ne doit pas faire tourner la boucle indéfiniment
+const MAX_PERIODS: i64 = 10_000;
+// Garde les dates calculées dans les limites de chrono, même sur MAX_PERIODS périodes
+const MAX_INTERVAL: u32 = 1_000;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+// Jour de BYDAY : "MO", ou avec un rang dans le mois/l'année ("2TU", "-1FR")
+#[derive(Clone, Copy, PartialEq)]
+struct ByDay {
+    ordinal: Option<i32>,
+    weekday: Weekday,
+}
+
+#[derive(Clone, PartialEq)]
+pub(crate) struct RecurrenceRule {
+    frequency: Frequency,
+    interval: u32,
+    by_day: Vec<ByDay>,
+    until: Option<NaiveDate>,
+    count: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct Occurrence {
+    // Rang dans la série, la première occurrence étant 1
+    index: usize,
+    date: String,
+}
+
+const WEEKDAYS: &[(&str, Weekday)] = &[
+    ("MO", Weekday::Mon),
+    ("TU", Weekday::Tue),
+    ("WE", Weekday::Wed),
+    ("TH", Weekday::Thu),
+    ("FR", Weekday::Fri),
+    ("SA", Weekday::Sat),
+    ("SU", Weekday::Sun),
+];
+
+// ---- Parsing ----
+
+fn parse_by_day(value: &str) -> Result<ByDay, String> {
+    let value = value.trim().to_uppercase();
+    // Les deux derniers caractères (et non octets) forment le jour : "€" ne doit pas couper un caractère
+    let split = value
+        .char_indices()
+        .rev()
+        .nth(1)
+        .map(|(i, _)| i)
+        .ok_or_else(|| format!("Invalid BYDAY value '{}'", value))?;
+    let (ordinal, day) = value.split_at(split);
+    let weekday = WEEKDAYS
+        .iter()
+        .find(|(code, _)| *code == day)
+        .map(|(_, weekday)| *weekday)
+        .ok_or_else(|| format!("Invalid BYDAY value '{}'", value))?;
+    let ordinal = match ordinal {
+        "" => None,
+        ordinal => match ordinal.trim_start_matches('+').parse::<i32>() {
+            Ok(n) if n != 0 && n.abs() <= 53 => Some(n),
+            _ => return Err(format!("Invalid BYDAY value '{}'", value)),
+        },
+    };
+    Ok(ByDay { ordinal, weekday })
+}
+
+// UNTIL accepte la forme RFC (20261231 ou 20261231T235959Z) et la forme ISO (2026-12-31)
+fn parse_until(value: &str) -> Result<NaiveDate, String> {
+    let date = value.split('T').next().unwrap_or_default();
+    NaiveDate::parse_from_str(date, "%Y%m%d")
+        .or_else(|_| NaiveDate::parse_from_str(date, "%Y-%m-%d"))
+        .map_err(|_| format!("Invalid UNTIL date '{}'", value))
+}
+
+pub(crate) fn parse_rule(rule: &str) -> Result<RecurrenceRule, String> {
+    let rule = rule.trim();
+    let rule = rule.strip_prefix("RRULE:").unwrap_or(rule);
+
+    let mut frequency = None;
+    let mut interval = 1;
+    let mut by_day = Vec::new();
+    let mut until = None;
+    let mut count = None;
+    for part in rule.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+        let (key, value) = part
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid recurrence rule part '{}'", part))?;
+        match key.trim().to_uppercase().as_str() {
+            "FREQ" => {
+                frequency = Some(match value.trim().to_uppercase().as_str() {
+                    "DAILY" => Frequency::Daily,
+                    "WEEKLY" => Frequency::Weekly,
+                    "MONTHLY" => Frequency::Monthly,
+                    "YEARLY" => Frequency::Yearly,
+                    other => return Err(format!("Unsupported frequency '{}'", other)),
+                })
+            }
+            "INTERVAL" => {
+                interval = value.trim().parse::<u32>().ok().filter(|i| (1..=MAX_INTERVAL).contains(i))
+                    .ok_or_else(|| format!("Invalid INTERVAL '{}' (expected 1 to {})", value, MAX_INTERVAL))?
+            }
+            "BYDAY" => {
+                by_day = value.split(',').map(parse_by_day).collect::<Result<_, _>>()?;
+            }
+            "UNTIL" => until = Some(parse_until(value.trim())?),
+            "COUNT" => {
+                count = Some(value.trim().parse::<u32>().ok().filter(|c| *c > 0)
+                    .ok_or_else(|| format!("Invalid COUNT '{}'", value))?)
+            }
+            other => return Err(format!("Unsupported recurrence rule part '{}'", other)),
+        }
+    }
+
+    let frequency = frequency.ok_or("The recurrence rule needs a FREQ")?;
+    if until.is_some() && count.is_some() {
+        return Err("A recurrence rule cannot have both UNTIL and COUNT".to_string());
+    }
+    // Comme dans la RFC, un rang n'a de sens que dans un mois ou une année
+    if matches!(frequency, Frequency::Daily | Frequency::Weekly) && by_day.iter().any(|d| d.ordinal.is_some()) {
+        return Err("BYDAY ranks (e.g. 2MO) are only allowed with monthly or yearly rules".to_string());
+    }
+    Ok(RecurrenceRule { frequency, interval, by_day, until, count })
+}
+
+// Forme normalisée, celle qui est enregistrée sur la tâche
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        write!(f, "FREQ={}", frequency)?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<String> = self.by_day.iter().map(|d| {
+                let code = WEEKDAYS.iter().find(|(_, w)| *w == d.weekday).map(|(c, _)| *c).unwrap_or_default();
+                match d.ordinal {
+                    Some(n) => format!("{}{}", n, code),
+                    None => code.to_string(),
+                }
+            }).collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%d"))?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        Ok(())
+    }
+}
+
+// ---- Occurrences ----
+
+fn last_day_of_month(year: i32, month: u32) -> NaiveDate {
+    let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1).map(|d| d - Duration::days(1)).unwrap_or(NaiveDate::MAX)
+}
+
+// Jours de [first, last] qui correspondent à BYDAY, rangs compris
+fn matching_days(first: NaiveDate, last: NaiveDate, by_day: &[ByDay]) -> Vec<NaiveDate> {
+    let mut dates = Vec::new();
+    for day in by_day {
+        let candidates: Vec<NaiveDate> = first
+            .iter_days()
+            .take_while(|d| *d <= last)
+            .filter(|d| d.weekday() == day.weekday)
+            .collect();
+        match day.ordinal {
+            None => dates.extend(candidates),
+            Some(n) if n > 0 => dates.extend(candidates.get(n as usize - 1)),
+            Some(n) => dates.extend(candidates.len().checked_sub(n.unsigned_abs() as usize).and_then(|i| candidates.get(i))),
+        }
+    }
+    dates.sort();
+    dates.dedup();
+    dates
+}
+
+// Dates candidates de la k-ième période (jour, semaine, mois ou année) à partir du début de la série.
+// None quand la période sort des dates représentables : la série s'arrête là.
+fn period_dates(rule: &RecurrenceRule, start: NaiveDate, k: i64) -> Option<Vec<NaiveDate>> {
+    let step = k.checked_mul(rule.interval as i64)?;
+    let dates = match rule.frequency {
+        Frequency::Daily => {
+            let date = start.checked_add_signed(Duration::days(step))?;
+            if rule.by_day.is_empty() || rule.by_day.iter().any(|d| d.weekday == date.weekday()) {
+                vec![date]
+            } else {
+                Vec::new()
+            }
+        }
+        Frequency::Weekly => {
+            // Semaines du lundi au dimanche
+            let weekday_offset = Duration::days(start.weekday().num_days_from_monday() as i64);
+            let monday = start.checked_sub_signed(weekday_offset)?.checked_add_signed(Duration::weeks(step))?;
+            if rule.by_day.is_empty() {
+                vec![monday.checked_add_signed(weekday_offset)?]
+            } else {
+                matching_days(monday, monday.checked_add_signed(Duration::days(6))?, &rule.by_day)
+            }
+        }
+        Frequency::Monthly => {
+            let months = (start.year() as i64 * 12 + start.month0() as i64).checked_add(step)?;
+            let (year, month) = (i32::try_from(months.div_euclid(12)).ok()?, months.rem_euclid(12) as u32 + 1);
+            let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+            if rule.by_day.is_empty() {
+                // Les mois sans ce jour (le 31, le 30 février) sont sautés, comme le prévoit la RFC
+                NaiveDate::from_ymd_opt(year, month, start.day()).into_iter().collect()
+            } else {
+                matching_days(first, last_day_of_month(year, month), &rule.by_day)
+            }
+        }
+        Frequency::Yearly => {
+            let year = start.year().checked_add(i32::try_from(step).ok()?)?;
+            let first = NaiveDate::from_ymd_opt(year, 1, 1)?;
+            let last = NaiveDate::from_ymd_opt(year, 12, 31)?;
+            if rule.by_day.is_empty() {
+                // Un 29 février ne revient que les années bissextiles
+                NaiveDate::from_ymd_opt(year, start.month(), start.day()).into_iter().collect()
+            } else {
+                matching_days(first, last, &rule.by_day)
+            }
+        }
+    };
+    Some(dates)
+}
+
+// Occurrences de la série commençant à `start` (toujours la première), postérieures à `after`
+pub(crate) fn occurrences(rule: &RecurrenceRule, start: NaiveDate, after: Option<NaiveDate>, limit: usize) -> Vec<(usize, NaiveDate)> {
+    let mut result = Vec::new();
+    if limit == 0 || rule.until.is_some_and(|until| start > until) {
+        return result;
+    }
+    let mut index = 1;
+    if after.is_none_or(|after| start > after) {
+        result.push((index, start));
+    }
+
+    for k in 0..MAX_PERIODS {
+        let dates = match period_dates(rule, start, k) {
+            Some(dates) => dates,
+            None => break,
+        };
+        for date in dates.into_iter().filter(|d| *d > start) {
+            if result.len() >= limit
+                || rule.until.is_some_and(|until| date > until)
+                || rule.count.is_some_and(|count| index >= count as usize)
+            {
+                return result;
+            }
+            index += 1;
+            if after.is_none_or(|after| date > after) {
+                result.push((index, date));
+            }
+        }
+    }
+    result
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date.get(..10).unwrap_or(date), "%Y-%m-%d").ok()
+}
+
+fn today() -> NaiveDate {
+    chrono::Local::now().naive_local().date()
+}
+
+// ---- Series ----
+
+struct Series {
+    rule: RecurrenceRule,
+    start: NaiveDate,
+    mode: String,
+    next_occurrence_id: Option<i64>,
+}
+
+fn task_series(conn: &Connection, id: i64) -> Result<Option<Series>, String> {
+    let (rule, start, mode, next_occurrence_id): (Option<String>, Option<String>, Option<String>, Option<i64>) = conn
+        .query_row(
+            "SELECT recurrence, recurrence_start, recurrence_mode, next_occurrence_id FROM tasks WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    match (rule, start.as_deref().and_then(parse_date)) {
+        (Some(rule), Some(start)) => Ok(Some(Series {
+            rule: parse_rule(&rule)?,
+            start,
+            mode: mode.unwrap_or_else(|| MODE_COMPLETION.to_string()),
+            next_occurrence_id,
+        })),
+        _ => Ok(None),
+    }
+}
+
+// Crée l'occurrence qui suit la tâche, si la série n'est pas terminée et qu'elle n'existe pas déjà
+pub(crate) fn create_next_occurrence(conn: &Connection, id: i64) -> Result<Option<i64>, String> {
+    let series = match task_series(conn, id)? {
+        Some(series) if series.next_occurrence_id.is_none() => series,
+        _ => return Ok(None),
+    };
+    let task = get_task(conn, id as i32)?;
+    let after = parse_date(&task.due_date).unwrap_or(series.start);
+    let next_date = match occurrences(&series.rule, series.start, Some(after), 1).first() {
+        Some((_, date)) => *date,
+        None => return Ok(None),
+    };
+
+    let current_date = chrono::Local::now().naive_local().to_string();
+    conn.execute(
+        "INSERT INTO tasks (title, description, status, priority, due_date, creation_date, category, updated_at, parent_id, recurrence, recurrence_start, recurrence_mode)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?6, ?8, ?9, ?10, ?11)",
+        params![
+            task.title,
+            task.description,
+            OCCURRENCE_STATUS,
+            task.priority,
+            next_date.format("%Y-%m-%d").to_string(),
+            current_date,
+            task.category,
+            task.parent_id,
+            series.rule.to_string(),
+            series.start.format("%Y-%m-%d").to_string(),
+            series.mode
+        ],
+    ).map_err(|e| e.to_string())?;
+    let next_id = conn.last_insert_rowid();
+
+    // La liste de points à cocher est reprise, décochée
+    conn.execute(
+        "INSERT INTO task_checklist_items (task_id, label, done, position)
+         SELECT ?1, label, 0, position FROM task_checklist_items WHERE task_id = ?2",
+        params![next_id, id],
+    ).map_err(|e| e.to_string())?;
+    copy_relative_reminders(conn, id, next_id)?;
+    conn.execute(
+        "UPDATE tasks SET next_occurrence_id = ?1 WHERE id = ?2",
+        params![next_id, id],
+    ).map_err(|e| e.to_string())?;
+    Ok(Some(next_id))
+}
+
+// Mode "schedule" : crée les occurrences arrivées à échéance, même si la précédente n'est pas terminée.
+// Une application restée fermée longtemps rattrape toutes les occurrences manquées.
+pub(crate) fn create_scheduled_occurrences() -> Result<Vec<Task>, String> {
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let today = today().format("%Y-%m-%d").to_string();
+
+    let mut created = Vec::new();
+    // Une série terminée reste due : on ne l'examine qu'une fois
+    let mut examined = HashSet::new();
+    loop {
+        let mut stmt = tx.prepare(
+            "SELECT id FROM tasks WHERE recurrence IS NOT NULL AND recurrence_mode = ?1
+             AND next_occurrence_id IS NULL AND due_date != '' AND substr(due_date, 1, 10) <= ?2"
+        ).map_err(|e| e.to_string())?;
+        let due: Vec<i64> = stmt
+            .query_map(params![MODE_SCHEDULE, today], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .collect::<rusqlite::Result<Vec<i64>>>()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|id| examined.insert(*id))
+            .collect();
+        if due.is_empty() {
+            break;
+        }
+        for id in due {
+            created.extend(create_next_occurrence(&tx, id)?);
+        }
+    }
+
+    let tasks = created.into_iter().map(|id| get_task(&tx, id as i32)).collect::<Result<_, _>>()?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(tasks)
+}
+
+// ---- Commands ----
+
+// Définit (ou retire avec None) la règle de récurrence ; la série commence à l'échéance de la tâche
+#[command]
+pub fn set_task_recurrence(id: i64, rule: Option<String>, mode: Option<String>) -> Result<Task, String> {
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let task = get_task(&conn, id as i32)?;
+    let current_date = chrono::Local::now().naive_local().to_string();
+
+    let rule = match rule.filter(|r| !r.trim().is_empty()) {
+        Some(rule) => parse_rule(&rule)?,
+        None => {
+            conn.execute(
+                "UPDATE tasks SET recurrence = NULL, recurrence_start = NULL, recurrence_mode = NULL, updated_at = ?1 WHERE id = ?2",
+                params![current_date, id],
+            ).map_err(|e| e.to_string())?;
+            return get_task(&conn, id as i32);
+        }
+    };
+    let mode = mode.unwrap_or_else(|| MODE_COMPLETION.to_string());
+    if mode != MODE_COMPLETION && mode != MODE_SCHEDULE {
+        return Err(format!("Unsupported recurrence mode '{}' (expected '{}' or '{}')", mode, MODE_COMPLETION, MODE_SCHEDULE));
+    }
+    let start = parse_date(&task.due_date).ok_or("A recurring task needs a due date")?;
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE tasks SET recurrence = ?1, recurrence_start = ?2, recurrence_mode = ?3, updated_at = ?4 WHERE id = ?5",
+        params![rule.to_string(), start.format("%Y-%m-%d").to_string(), mode, current_date, id],
+    ).map_err(|e| e.to_string())?;
+    // Une tâche déjà terminée enchaîne tout de suite sur l'occurrence suivante
+    if is_done_status(&tx, &task.status)? {
+        create_next_occurrence(&tx, id)?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    get_task(&conn, id as i32)
+}
+
+// Aperçu des prochaines occurrences d'une règle, avant même de l'enregistrer
+#[command]
+pub fn preview_recurrence(rule: String, start: String, limit: Option<usize>) -> Result<Vec<Occurrence>, String> {
+    let rule = parse_rule(&rule)?;
+    let start = parse_date(&start).ok_or_else(|| format!("Invalid start date '{}'", start))?;
+    Ok(occurrences(&rule, start, None, limit.unwrap_or(DEFAULT_PREVIEW_LIMIT))
+        .into_iter()
+        .map(|(index, date)| Occurrence { index, date: date.format("%Y-%m-%d").to_string() })
+        .collect())
+}
+
+// Prochaines occurrences d'une tâche récurrente, après son échéance
+#[command]
+pub fn preview_task_occurrences(id: i64, limit: Option<usize>) -> Result<Vec<Occurrence>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let task = get_task(&conn, id as i32)?;
+    let series = task_series(&conn, id)?.ok_or_else(|| format!("Task {} is not recurring", id))?;
+    let after = parse_date(&task.due_date).unwrap_or(series.start);
+    Ok(occurrences(&series.rule, series.start, Some(after), limit.unwrap_or(DEFAULT_PREVIEW_LIMIT))
+        .into_iter()
+        .map(|(index, date)| Occurrence { index, date: date.format("%Y-%m-%d").to_string() })
+        .collect())
+}
+
+#[command]
+pub fn generate_recurring_tasks() -> Result<Vec<Task>, String> {
+    create_scheduled_occurrences()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn dates(rule: &str, start: &str, limit: usize) -> Vec<String> {
+        let rule = parse_rule(rule).unwrap();
+        occurrences(&rule, date(start), None, limit).into_iter().map(|(_, d)| d.to_string()).collect()
+    }
+
+    #[test]
+    fn monthly_on_the_31st_skips_short_months() {
+        assert_eq!(
+            dates("FREQ=MONTHLY", "2025-01-31", 4),
+            ["2025-01-31", "2025-03-31", "2025-05-31", "2025-07-31"]
+        );
+    }
+
+    #[test]
+    fn yearly_on_february_29th_only_falls_on_leap_years() {
+        assert_eq!(dates("FREQ=YEARLY", "2024-02-29", 3), ["2024-02-29", "2028-02-29", "2032-02-29"]);
+    }
+
+    #[test]
+    fn monthly_last_friday() {
+        assert_eq!(
+            dates("FREQ=MONTHLY;BYDAY=-1FR", "2025-01-31", 4),
+            ["2025-01-31", "2025-02-28", "2025-03-28", "2025-04-25"]
+        );
+    }
+
+    #[test]
+    fn count_includes_the_first_occurrence() {
+        assert_eq!(dates("FREQ=DAILY;COUNT=3", "2025-01-01", 10), ["2025-01-01", "2025-01-02", "2025-01-03"]);
+
+        // Les occurrences passées comptent dans COUNT même quand elles ne sont pas renvoyées
+        let rule = parse_rule("FREQ=DAILY;COUNT=3").unwrap();
+        let next = occurrences(&rule, date("2025-01-01"), Some(date("2025-01-02")), 10);
+        assert_eq!(next, [(3, date("2025-01-03"))]);
+    }
+
+    #[test]
+    fn until_is_inclusive() {
+        assert_eq!(dates("FREQ=DAILY;UNTIL=20250103", "2025-01-01", 10), ["2025-01-01", "2025-01-02", "2025-01-03"]);
+        assert!(dates("FREQ=DAILY;UNTIL=20241231", "2025-01-01", 10).is_empty());
+    }
+
+    #[test]
+    fn weekly_by_day_includes_the_rest_of_the_start_week() {
+        // 2025-01-01 est un mercredi : le lundi 30 décembre précède le début de la série
+        assert_eq!(
+            dates("FREQ=WEEKLY;BYDAY=MO,WE,FR", "2025-01-01", 4),
+            ["2025-01-01", "2025-01-03", "2025-01-06", "2025-01-08"]
+        );
+    }
+
+    #[test]
+    fn interval_is_bounded() {
+        assert!(parse_rule("FREQ=DAILY;INTERVAL=0").is_err());
+        assert!(parse_rule(&format!("FREQ=DAILY;INTERVAL={}", MAX_INTERVAL + 1)).is_err());
+        // La série s'arrête à la limite des dates représentables au lieu de paniquer
+        let rule = parse_rule(&format!("FREQ=YEARLY;INTERVAL={}", MAX_INTERVAL)).unwrap();
+        assert!(occurrences(&rule, date("2025-01-01"), None, usize::MAX).len() < MAX_PERIODS as usize);
+    }
+
+    #[test]
+    fn non_ascii_by_day_is_rejected() {
+        assert!(parse_rule("FREQ=WEEKLY;BYDAY=MO€").is_err());
+        assert!(parse_rule("FREQ=WEEKLY;BYDAY=€").is_err());
+        assert!(parse_rule("FREQ=MONTHLY;BYDAY=€FR").is_err());
+    }
+
+    #[test]
+    fn rule_is_normalized() {
+        assert_eq!(
+            parse_rule("RRULE:freq=monthly;interval=2;byday=-1fr;count=5").map(|r| r.to_string()),
+            Ok("FREQ=MONTHLY;INTERVAL=2;BYDAY=-1FR;COUNT=5".to_string())
+        );
+    }
+}
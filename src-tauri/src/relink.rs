@@ -0,0 +1,180 @@
+// ------------------------------------ Broken attachments -----------------------------------------
+//
+// Repère les pièces jointes dont le fichier n'existe plus, cherche des remplaçants dans un dossier
+// choisi par l'utilisateur (même contenu ou même nom) et réassocie les fichiers en lot.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::attachments::sync_legacy_column;
+use crate::hash_file;
+use crate::vault::{is_vault_reference, resolve_file_reference, store_in_vault, vault_year};
+
+#[derive(Serialize, Clone)]
+pub struct BrokenAttachment {
+    attachment_id: i64,
+    record_type: String,
+    record_id: i64,
+    reference: String,
+    original_name: String,
+    // Hash du contenu attendu quand on le connaît
+    expected_sha256: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RelinkCandidate {
+    path: String,
+    // "same_content" ou "same_name"
+    reason: String,
+}
+
+#[derive(Serialize)]
+pub struct RelinkProposal {
+    attachment: BrokenAttachment,
+    candidates: Vec<RelinkCandidate>,
+}
+
+#[derive(Deserialize)]
+pub struct Relink {
+    attachment_id: i64,
+    new_path: String,
+}
+
+fn scan(conn: &Connection) -> Result<Vec<BrokenAttachment>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, record_type, record_id, file_reference, original_name, sha256 FROM attachments ORDER BY record_type, record_id, position"
+    ).map_err(|e| e.to_string())?;
+    let attachment_iter = stmt.query_map([], |row| {
+        Ok(BrokenAttachment {
+            attachment_id: row.get(0)?,
+            record_type: row.get(1)?,
+            record_id: row.get(2)?,
+            reference: row.get(3)?,
+            original_name: row.get(4)?,
+            expected_sha256: row.get(5)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut broken = Vec::new();
+    for attachment in attachment_iter {
+        let mut attachment = attachment.map_err(|e| e.to_string())?;
+        if resolve_file_reference(&attachment.reference).map(|p| p.is_file()).unwrap_or(false) {
+            continue;
+        }
+        // Le nom d'un fichier du coffre est le hash de son contenu
+        if attachment.expected_sha256.is_none() && is_vault_reference(&attachment.reference) {
+            attachment.expected_sha256 = Path::new(&attachment.reference).file_stem().map(|s| s.to_string_lossy().to_string());
+        }
+        broken.push(attachment);
+    }
+    Ok(broken)
+}
+
+fn collect_files(directory: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    for entry in fs::read_dir(directory).map_err(|e| e.to_string())? {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[command]
+pub fn scan_broken_attachments() -> Result<Vec<BrokenAttachment>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    scan(&conn)
+}
+
+#[command]
+pub fn find_relink_candidates(directory: String) -> Result<Vec<RelinkProposal>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let broken = scan(&conn)?;
+    if broken.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    collect_files(Path::new(&directory), &mut files)?;
+
+    let mut by_name: HashMap<String, Vec<&PathBuf>> = HashMap::new();
+    for file in &files {
+        if let Some(name) = file.file_name() {
+            by_name.entry(name.to_string_lossy().to_lowercase()).or_default().push(file);
+        }
+    }
+
+    // Le dossier n'est haché que si au moins un fichier manquant a un contenu connu
+    let mut by_hash: HashMap<String, Vec<&PathBuf>> = HashMap::new();
+    if broken.iter().any(|b| b.expected_sha256.is_some()) {
+        for file in &files {
+            if let Ok(sha256) = hash_file(file) {
+                by_hash.entry(sha256).or_default().push(file);
+            }
+        }
+    }
+
+    let mut proposals = Vec::new();
+    for attachment in broken {
+        let mut candidates: Vec<RelinkCandidate> = Vec::new();
+        if let Some(matches) = attachment.expected_sha256.as_ref().and_then(|h| by_hash.get(h)) {
+            for path in matches {
+                candidates.push(RelinkCandidate { path: path.to_string_lossy().to_string(), reason: "same_content".to_string() });
+            }
+        }
+        for path in by_name.get(&attachment.original_name.to_lowercase()).into_iter().flatten() {
+            let path = path.to_string_lossy().to_string();
+            if !candidates.iter().any(|c| c.path == path) {
+                candidates.push(RelinkCandidate { path, reason: "same_name".to_string() });
+            }
+        }
+        proposals.push(RelinkProposal { attachment, candidates });
+    }
+    Ok(proposals)
+}
+
+#[command]
+pub fn relink_attachments(relinks: Vec<Relink>) -> Result<usize, String> {
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    for relink in &relinks {
+        let new_path = Path::new(&relink.new_path);
+        if !new_path.is_file() {
+            return Err(format!("File not found: {}", relink.new_path));
+        }
+        let (record_type, record_id, added_date, old_reference): (String, i64, String, String) = tx
+            .query_row(
+                "SELECT record_type, record_id, added_date, file_reference FROM attachments WHERE id = ?1",
+                params![relink.attachment_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .map_err(|e| e.to_string())?;
+
+        // Le fichier retrouvé est rangé dans le coffre, comme à l'ajout
+        let reference = store_in_vault(&relink.new_path, &record_type, vault_year(Some(&added_date)), false)?;
+        let size = fs::metadata(new_path).map_err(|e| e.to_string())?.len() as i64;
+        tx.execute(
+            "UPDATE attachments SET file_reference = ?1, sha256 = ?2, size = ?3, extracted_text = NULL, text_extracted_at = NULL, extraction_error = NULL WHERE id = ?4",
+            params![reference, hash_file(new_path)?, size, relink.attachment_id],
+        ).map_err(|e| e.to_string())?;
+        // Les versions du document qui pointaient vers le fichier perdu suivent la pièce jointe
+        if record_type == "admin_documents" {
+            tx.execute(
+                "UPDATE admin_document_versions SET file_reference = ?1 WHERE admin_document_id = ?2 AND file_reference = ?3",
+                params![reference, record_id, old_reference],
+            ).map_err(|e| e.to_string())?;
+        }
+        sync_legacy_column(&tx, &record_type, record_id)?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(relinks.len())
+}
@@ -0,0 +1,347 @@
+// ------------------------------------ Task reminders -----------------------------------------
+//
+// Rappels de tâches : à une date et heure précises, ou un délai avant l'échéance. Un planificateur
+// tourne dans l'application et affiche une notification système au bon moment ; les rappels passés
+// pendant que l'application était fermée sont rattrapés au démarrage.
+
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use log::error;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::api::notification::Notification;
+use tauri::{command, AppHandle, Manager, State};
+
+use crate::{done_statuses, expiry, get_task, recurrence, DoneStatuses};
+
+// Le planificateur se réveille au plus tard après ce délai (changement d'échéance, de jour...)
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+// Heure utilisée pour une échéance sans heure
+const DEFAULT_DUE_TIME: (u32, u32) = (9, 0);
+const DEFAULT_SNOOZE_MINUTES: i64 = 10;
+// Un an bissextile : borne les délais avant l'échéance et les reports
+const MAX_REMINDER_MINUTES: i64 = 366 * 24 * 60;
+// Au-delà, les rappels rattrapés sont regroupés dans une seule notification
+const MAX_SEPARATE_NOTIFICATIONS: usize = 3;
+const FIRED_EVENT: &str = "task-reminders-fired";
+const DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub struct ReminderScheduler {
+    wake: Mutex<Sender<()>>,
+}
+
+impl ReminderScheduler {
+    pub(crate) fn wake(&self) -> Result<(), String> {
+        self.wake.lock().map_err(|e| e.to_string())?.send(()).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct Reminder {
+    id: i64,
+    task_id: i64,
+    task_title: String,
+    task_due_date: String,
+    remind_at: Option<String>,
+    offset_minutes: Option<i64>,
+    snoozed_until: Option<String>,
+    fired_at: Option<String>,
+    dismissed_at: Option<String>,
+    // Calculé : prochain déclenchement (None si l'échéance de la tâche n'est pas une date)
+    fire_at: Option<String>,
+    // Calculé : "pending", "fired" ou "dismissed"
+    state: String,
+    #[serde(skip)]
+    task_done: bool,
+}
+
+fn now() -> NaiveDateTime {
+    chrono::Local::now().naive_local()
+}
+
+// Accepte les formes "2026-10-19 14:30[:00]", "2026-10-19T14:30" (champ datetime-local) et "2026-10-19"
+fn parse_date_time(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            let date = NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()?;
+            Some(date.and_time(NaiveTime::from_hms_opt(DEFAULT_DUE_TIME.0, DEFAULT_DUE_TIME.1, 0)?))
+        })
+}
+
+fn reminder_from_row(row: &rusqlite::Row, done: &DoneStatuses) -> rusqlite::Result<Reminder> {
+    let remind_at: Option<String> = row.get(4)?;
+    let offset_minutes: Option<i64> = row.get(5)?;
+    let snoozed_until: Option<String> = row.get(6)?;
+    let fired_at: Option<String> = row.get(7)?;
+    let dismissed_at: Option<String> = row.get(8)?;
+    let task_due_date: String = row.get(3)?;
+    let task_status: String = row.get(9)?;
+
+    // Un rappel reporté part à la fin du report, sinon à l'heure fixée ou avant l'échéance
+    let fire_at = snoozed_until.as_deref().and_then(parse_date_time)
+        .or_else(|| remind_at.as_deref().and_then(parse_date_time))
+        .or_else(|| parse_date_time(&task_due_date)?.checked_sub_signed(chrono::Duration::try_minutes(offset_minutes?)?));
+    let state = if dismissed_at.is_some() {
+        "dismissed"
+    } else if fired_at.is_some() {
+        "fired"
+    } else {
+        "pending"
+    };
+    Ok(Reminder {
+        id: row.get(0)?,
+        task_id: row.get(1)?,
+        task_title: row.get(2)?,
+        task_due_date,
+        remind_at,
+        offset_minutes,
+        snoozed_until,
+        fired_at,
+        dismissed_at,
+        fire_at: fire_at.map(|d| d.format(DATE_TIME_FORMAT).to_string()),
+        state: state.to_string(),
+        task_done: done.contains(&task_status),
+    })
+}
+
+fn query_reminders(conn: &Connection, filter: &str, params: &[&dyn rusqlite::ToSql]) -> Result<Vec<Reminder>, String> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT r.id, r.task_id, t.title, t.due_date, r.remind_at, r.offset_minutes, r.snoozed_until, r.fired_at, r.dismissed_at, t.status
+         FROM task_reminders r JOIN tasks t ON t.id = r.task_id {} ORDER BY r.id",
+        filter
+    )).map_err(|e| e.to_string())?;
+    let done = done_statuses(conn)?;
+    let reminder_iter = stmt.query_map(params, |row| reminder_from_row(row, &done)).map_err(|e| e.to_string())?;
+
+    let mut reminders = Vec::new();
+    for reminder in reminder_iter {
+        reminders.push(reminder.map_err(|e| e.to_string())?);
+    }
+    Ok(reminders)
+}
+
+fn get_reminder(conn: &Connection, id: i64) -> Result<Reminder, String> {
+    query_reminders(conn, "WHERE r.id = ?1", &[&id])?
+        .pop()
+        .ok_or_else(|| format!("Reminder {} not found", id))
+}
+
+// Les rappels relatifs à l'échéance suivent la tâche dans sa prochaine occurrence
+pub(crate) fn copy_relative_reminders(conn: &Connection, from_task_id: i64, to_task_id: i64) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO task_reminders (task_id, offset_minutes)
+         SELECT ?1, offset_minutes FROM task_reminders WHERE task_id = ?2 AND offset_minutes IS NOT NULL",
+        params![to_task_id, from_task_id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Échéance modifiée : les rappels relatifs repartent de zéro pour la nouvelle date
+pub(crate) fn reset_relative_reminders(conn: &Connection, task_id: i64) -> Result<(), String> {
+    conn.execute(
+        "UPDATE task_reminders SET fired_at = NULL, snoozed_until = NULL, dismissed_at = NULL
+         WHERE task_id = ?1 AND offset_minutes IS NOT NULL",
+        params![task_id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub(crate) fn delete_task_reminders(conn: &Connection, task_id: i64) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM task_reminders WHERE task_id = ?1",
+        params![task_id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// ---- Scheduler ----
+
+fn notify(app: &AppHandle, title: &str, body: &str) {
+    let identifier = app.config().tauri.bundle.identifier.clone();
+    if let Err(e) = Notification::new(identifier).title(title).body(body).show() {
+        error!("Failed to show notification: {}", e);
+    }
+}
+
+// Affiche les rappels arrivés à échéance et renvoie l'heure du prochain rappel en attente
+fn fire_due_reminders(app: &AppHandle) -> Result<Option<NaiveDateTime>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let now = now();
+    let pending: Vec<(NaiveDateTime, Reminder)> = query_reminders(&conn, "WHERE r.fired_at IS NULL AND r.dismissed_at IS NULL", &[])?
+        .into_iter()
+        .filter(|r| !r.task_done)
+        .filter_map(|r| Some((r.fire_at.as_deref().and_then(parse_date_time)?, r)))
+        .collect();
+    let (due, waiting): (Vec<_>, Vec<_>) = pending.into_iter().partition(|(fire_at, _)| *fire_at <= now);
+    let next = waiting.iter().map(|(fire_at, _)| *fire_at).min();
+    if due.is_empty() {
+        return Ok(next);
+    }
+
+    // Après une longue fermeture, une seule notification plutôt qu'une rafale
+    if due.len() > MAX_SEPARATE_NOTIFICATIONS {
+        let titles: Vec<&str> = due.iter().map(|(_, r)| r.task_title.as_str()).collect();
+        notify(app, &format!("{} task reminders", due.len()), &titles.join("\n"));
+    } else {
+        for (_, reminder) in &due {
+            let body = match reminder.task_due_date.as_str() {
+                "" => "Reminder".to_string(),
+                due_date => format!("Due {}", due_date),
+            };
+            notify(app, &reminder.task_title, &body);
+        }
+    }
+
+    let fired_at = now.format(DATE_TIME_FORMAT).to_string();
+    let mut fired = Vec::new();
+    for (_, reminder) in due {
+        conn.execute(
+            "UPDATE task_reminders SET fired_at = ?1 WHERE id = ?2",
+            params![fired_at, reminder.id],
+        ).map_err(|e| e.to_string())?;
+        fired.push(get_reminder(&conn, reminder.id)?);
+    }
+    let _ = app.emit_all(FIRED_EVENT, fired);
+    Ok(next)
+}
+
+// Lance le planificateur : un passage au démarrage (rattrapage), puis au prochain rappel,
+// à chaque modification ou au plus tard après CHECK_INTERVAL
+pub(crate) fn start(app: AppHandle) -> ReminderScheduler {
+    let (wake, requests) = mpsc::channel();
+    thread::spawn(move || {
+        let mut today = now().date();
+        loop {
+            let next = fire_due_reminders(&app).unwrap_or_else(|e| {
+                error!("Task reminders failed: {}", e);
+                None
+            });
+            // Les tâches récurrentes planifiées et les renouvellements sont créés au changement de jour
+            if now().date() != today {
+                today = now().date();
+                if let Err(e) = recurrence::create_scheduled_occurrences() {
+                    error!("Failed to create recurring tasks: {}", e);
+                }
+                if let Err(e) = expiry::create_renewal_tasks() {
+                    error!("Failed to create renewal tasks: {}", e);
+                }
+            }
+
+            let timeout = next
+                .and_then(|next| (next - now()).to_std().ok())
+                .map_or(CHECK_INTERVAL, |until_next| until_next.min(CHECK_INTERVAL));
+            match requests.recv_timeout(timeout) {
+                Ok(()) | Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    });
+    ReminderScheduler { wake: Mutex::new(wake) }
+}
+
+// ---- Commands ----
+
+// Rappel à une date et heure précises (remind_at) ou un nombre de minutes avant l'échéance
+#[command]
+pub fn add_task_reminder(
+    task_id: i64,
+    remind_at: Option<String>,
+    offset_minutes: Option<i64>,
+    scheduler: State<'_, ReminderScheduler>
+) -> Result<Vec<Reminder>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let task = get_task(&conn, task_id as i32)?;
+    let remind_at = match (remind_at.filter(|r| !r.trim().is_empty()), offset_minutes) {
+        (Some(_), Some(_)) => return Err("A reminder has either a time or an offset before the due date, not both".to_string()),
+        (None, None) => return Err("A reminder needs a time or an offset before the due date".to_string()),
+        (Some(remind_at), None) => {
+            let remind_at = parse_date_time(&remind_at).ok_or_else(|| format!("Invalid reminder time '{}'", remind_at))?;
+            Some(remind_at.format(DATE_TIME_FORMAT).to_string())
+        }
+        (None, Some(offset)) => {
+            if !(0..=MAX_REMINDER_MINUTES).contains(&offset) {
+                return Err(format!("The reminder offset must be between 0 and {} minutes", MAX_REMINDER_MINUTES));
+            }
+            if parse_date_time(&task.due_date).is_none() {
+                return Err(format!("Task {} has no due date to remind before", task_id));
+            }
+            None
+        }
+    };
+
+    conn.execute(
+        "INSERT INTO task_reminders (task_id, remind_at, offset_minutes) VALUES (?1, ?2, ?3)",
+        params![task_id, remind_at, offset_minutes],
+    ).map_err(|e| e.to_string())?;
+    scheduler.wake()?;
+    get_task_reminders(task_id)
+}
+
+#[command]
+pub fn get_task_reminders(task_id: i64) -> Result<Vec<Reminder>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    query_reminders(&conn, "WHERE r.task_id = ?1", &[&task_id])
+}
+
+// Rappels affichés qui n'ont été ni reportés ni écartés
+#[command]
+pub fn get_fired_reminders() -> Result<Vec<Reminder>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    query_reminders(&conn, "WHERE r.fired_at IS NOT NULL AND r.dismissed_at IS NULL", &[])
+}
+
+#[command]
+pub fn delete_task_reminder(id: i64, scheduler: State<'_, ReminderScheduler>) -> Result<(), String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM task_reminders WHERE id = ?1",
+        params![id],
+    ).map_err(|e| e.to_string())?;
+    scheduler.wake()
+}
+
+// Reporte le rappel de quelques minutes ; il repasse en attente
+#[command]
+pub fn snooze_reminder(id: i64, minutes: Option<i64>, scheduler: State<'_, ReminderScheduler>) -> Result<Reminder, String> {
+    let minutes = minutes.unwrap_or(DEFAULT_SNOOZE_MINUTES);
+    if !(1..=MAX_REMINDER_MINUTES).contains(&minutes) {
+        return Err(format!("The snooze duration must be between 1 and {} minutes", MAX_REMINDER_MINUTES));
+    }
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    get_reminder(&conn, id)?;
+    let snoozed_until = now()
+        .checked_add_signed(chrono::Duration::minutes(minutes))
+        .ok_or("The snooze ends after the last supported date")?
+        .format(DATE_TIME_FORMAT)
+        .to_string();
+    conn.execute(
+        "UPDATE task_reminders SET snoozed_until = ?1, fired_at = NULL, dismissed_at = NULL WHERE id = ?2",
+        params![snoozed_until, id],
+    ).map_err(|e| e.to_string())?;
+    scheduler.wake()?;
+    get_reminder(&conn, id)
+}
+
+#[command]
+pub fn dismiss_reminder(id: i64) -> Result<Reminder, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let dismissed = conn
+        .query_row("SELECT dismissed_at FROM task_reminders WHERE id = ?1", params![id], |row| row.get::<_, Option<String>>(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Reminder {} not found", id))?;
+    if dismissed.is_none() {
+        conn.execute(
+            "UPDATE task_reminders SET dismissed_at = ?1 WHERE id = ?2",
+            params![now().format(DATE_TIME_FORMAT).to_string(), id],
+        ).map_err(|e| e.to_string())?;
+    }
+    get_reminder(&conn, id)
+}
@@ -0,0 +1,345 @@
+// ------------------------------------ Retention policies -----------------------------------------
+//
+// Durées de conservation par type d'enregistrement et par catégorie (par exemple 10 ans après le
+// paiement pour les factures). Les enregistrements dont la durée est écoulée sont listés, puis
+// supprimés avec leurs fichiers du coffre après confirmation ; chaque suppression laisse une entrée
+// dans le journal d'audit.
+
+use std::fs;
+
+use chrono::{Datelike, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::command;
+
+use crate::attachments::delete_record_attachments;
+use crate::vault::{is_vault_reference, resolve_file_reference, FILE_COLUMNS};
+use crate::versions::delete_document_versions;
+
+// Au-delà, une durée de conservation est une erreur de saisie
+const MAX_KEEP_YEARS: i64 = 100;
+
+struct RetentionSource {
+    record_type: &'static str,
+    category_column: &'static str,
+    label_column: &'static str,
+    // Dates à partir desquelles la durée peut être comptée : (nom, colonne)
+    anchors: &'static [(&'static str, &'static str)],
+}
+
+const RETENTION_SOURCES: &[RetentionSource] = &[
+    RetentionSource {
+        record_type: "invoices",
+        category_column: "category",
+        label_column: "creditor",
+        anchors: &[("payment", "payment_date"), ("due", "due_date")],
+    },
+    RetentionSource {
+        record_type: "revenues",
+        category_column: "revenue_type",
+        label_column: "source",
+        anchors: &[("receipt", "receipt_date")],
+    },
+    RetentionSource {
+        record_type: "admin_documents",
+        category_column: "admin_doc_category",
+        label_column: "admin_doc_description",
+        anchors: &[("receipt", "received_at"), ("expiry", "expiry_date"), ("archive", "archived_at")],
+    },
+];
+
+#[derive(Serialize)]
+pub struct RetentionRule {
+    id: i64,
+    record_type: String,
+    // Règle par défaut du type quand aucune catégorie n'est donnée
+    category: Option<String>,
+    keep_years: i64,
+    anchor: String,
+}
+
+#[derive(Serialize)]
+pub struct RetentionCandidate {
+    record_type: String,
+    record_id: i64,
+    label: String,
+    category: Option<String>,
+    rule_id: i64,
+    reference_date: String,
+    retention_end: String,
+    files: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PurgeTarget {
+    record_type: String,
+    record_id: i64,
+}
+
+#[derive(Serialize)]
+pub struct PurgeSummary {
+    purged: usize,
+    deleted_files: usize,
+    // Fichiers du coffre qui n'ont pas pu être supprimés (les enregistrements le sont)
+    failed_files: Vec<String>,
+}
+
+fn source_for(record_type: &str) -> Result<&'static RetentionSource, String> {
+    RETENTION_SOURCES
+        .iter()
+        .find(|s| s.record_type == record_type)
+        .ok_or_else(|| format!("Unsupported record type: {}", record_type))
+}
+
+// Ajoute des années ; un 29 février devient un 28 février. None si la date n'est pas représentable
+fn add_years(date: NaiveDate, years: i64) -> Option<NaiveDate> {
+    let year = i32::try_from(years).ok().and_then(|years| date.year().checked_add(years))?;
+    date.with_year(year)
+        .or_else(|| NaiveDate::from_ymd_opt(year, date.month(), 28))
+}
+
+fn query_rules(conn: &Connection) -> Result<Vec<RetentionRule>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, record_type, category, keep_years, anchor FROM retention_rules ORDER BY record_type, category"
+    ).map_err(|e| e.to_string())?;
+    let rule_iter = stmt.query_map([], |row| {
+        Ok(RetentionRule {
+            id: row.get(0)?,
+            record_type: row.get(1)?,
+            category: row.get(2)?,
+            keep_years: row.get(3)?,
+            anchor: row.get(4)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut rules = Vec::new();
+    for rule in rule_iter {
+        rules.push(rule.map_err(|e| e.to_string())?);
+    }
+    Ok(rules)
+}
+
+// Fichiers d'un enregistrement : pièces jointes et, pour un document, ses anciennes versions
+pub(crate) fn record_files(conn: &Connection, record_type: &str, record_id: i64) -> Result<Vec<String>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT file_reference FROM attachments WHERE record_type = ?1 AND record_id = ?2
+         UNION SELECT file_reference FROM admin_document_versions WHERE ?1 = 'admin_documents' AND admin_document_id = ?2"
+    ).map_err(|e| e.to_string())?;
+    let files = stmt
+        .query_map(params![record_type, record_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<String>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(files)
+}
+
+fn candidates(conn: &Connection) -> Result<Vec<RetentionCandidate>, String> {
+    let rules = query_rules(conn)?;
+    let today = chrono::Local::now().naive_local().date();
+    let mut candidates = Vec::new();
+
+    for source in RETENTION_SOURCES {
+        let columns: Vec<&str> = source.anchors.iter().map(|(_, column)| *column).collect();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, {}, {}, {} FROM {} ORDER BY id",
+            source.label_column, source.category_column, columns.join(", "), source.record_type
+        )).map_err(|e| e.to_string())?;
+        let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
+
+        while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+            let record_id: i64 = row.get(0).map_err(|e| e.to_string())?;
+            let label: Option<String> = row.get(1).map_err(|e| e.to_string())?;
+            let category: Option<String> = row.get(2).map_err(|e| e.to_string())?;
+
+            // Règle de la catégorie, sinon règle par défaut du type
+            let rule = rules
+                .iter()
+                .filter(|r| r.record_type == source.record_type)
+                .find(|r| r.category.is_some() && r.category == category)
+                .or_else(|| rules.iter().find(|r| r.record_type == source.record_type && r.category.is_none()));
+            let rule = match rule {
+                Some(rule) => rule,
+                None => continue,
+            };
+            let anchor_index = match source.anchors.iter().position(|(name, _)| *name == rule.anchor) {
+                Some(index) => index,
+                None => continue,
+            };
+            let reference_date: Option<String> = row.get(3 + anchor_index).map_err(|e| e.to_string())?;
+            let reference_date = match reference_date
+                .as_deref()
+                .and_then(|d| d.get(0..10))
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            {
+                Some(date) => date,
+                None => continue,
+            };
+
+            // Une fin de conservation incalculable ne rend jamais l'enregistrement supprimable
+            let retention_end = match add_years(reference_date, rule.keep_years) {
+                Some(end) if end < today => end,
+                _ => continue,
+            };
+            candidates.push(RetentionCandidate {
+                record_type: source.record_type.to_string(),
+                record_id,
+                label: label.unwrap_or_default(),
+                category,
+                rule_id: rule.id,
+                reference_date: reference_date.to_string(),
+                retention_end: retention_end.to_string(),
+                files: Vec::new(),
+            });
+        }
+    }
+
+    for candidate in &mut candidates {
+        candidate.files = record_files(conn, &candidate.record_type, candidate.record_id)?;
+    }
+    Ok(candidates)
+}
+
+// Un fichier du coffre peut être partagé entre plusieurs enregistrements (même contenu)
+fn is_file_referenced(conn: &Connection, reference: &str) -> Result<bool, String> {
+    let mut checks = vec![
+        "SELECT 1 FROM attachments WHERE file_reference = ?1".to_string(),
+        "SELECT 1 FROM admin_document_versions WHERE file_reference = ?1".to_string(),
+    ];
+    for (table, column, multi, _) in FILE_COLUMNS {
+        checks.push(if *multi {
+            format!("SELECT 1 FROM {} WHERE instr({}, ?1) > 0", table, column)
+        } else {
+            format!("SELECT 1 FROM {} WHERE {} = ?1", table, column)
+        });
+    }
+    for check in checks {
+        let found: Option<i64> = conn
+            .query_row(&format!("{} LIMIT 1", check), params![reference], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if found.is_some() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// Seuls les fichiers du coffre qui ne servent plus à aucun enregistrement sont supprimés ;
+// renvoie le nombre de fichiers supprimés et les références qui n'ont pas pu l'être
+pub(crate) fn remove_unreferenced_files(conn: &Connection, mut files: Vec<String>) -> Result<(usize, Vec<String>), String> {
+    files.sort();
+    files.dedup();
+    let mut deleted_files = 0;
+    let mut failed_files = Vec::new();
+    for reference in files.iter().filter(|r| is_vault_reference(r)) {
+        if is_file_referenced(conn, reference)? {
+            continue;
+        }
+        let path = resolve_file_reference(reference)?;
+        if !path.exists() {
+            continue;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => deleted_files += 1,
+            Err(_) => failed_files.push(reference.clone()),
+        }
+    }
+    Ok((deleted_files, failed_files))
+}
+
+#[command]
+pub fn add_retention_rule(record_type: String, category: Option<String>, keep_years: i64, anchor: String) -> Result<(), String> {
+    let source = source_for(&record_type)?;
+    if !source.anchors.iter().any(|(name, _)| *name == anchor) {
+        let anchors: Vec<&str> = source.anchors.iter().map(|(name, _)| *name).collect();
+        return Err(format!("Unknown date '{}' for {}, expected one of {}", anchor, record_type, anchors.join(", ")));
+    }
+    if !(0..=MAX_KEEP_YEARS).contains(&keep_years) {
+        return Err(format!("The retention period must be between 0 and {} years", MAX_KEEP_YEARS));
+    }
+    let category = category.filter(|c| !c.trim().is_empty());
+
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    // Une seule règle par type et catégorie : la nouvelle remplace l'ancienne
+    conn.execute(
+        "DELETE FROM retention_rules WHERE record_type = ?1 AND category IS ?2",
+        params![record_type, category],
+    ).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO retention_rules (record_type, category, keep_years, anchor) VALUES (?1, ?2, ?3, ?4)",
+        params![record_type, category, keep_years, anchor],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[command]
+pub fn get_retention_rules() -> Result<Vec<RetentionRule>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    query_rules(&conn)
+}
+
+#[command]
+pub fn delete_retention_rule(id: i64) -> Result<(), String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM retention_rules WHERE id = ?1",
+        params![id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[command]
+pub fn get_retention_candidates() -> Result<Vec<RetentionCandidate>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    candidates(&conn)
+}
+
+// Supprime les enregistrements choisis parmi ceux dont la durée de conservation est écoulée
+#[command]
+pub fn purge_records(targets: Vec<PurgeTarget>, confirm: bool) -> Result<PurgeSummary, String> {
+    if !confirm {
+        return Err("The purge must be confirmed".to_string());
+    }
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let expired = candidates(&conn)?;
+
+    // Les cibles sont revérifiées : rien n'est supprimé avant la fin de sa durée de conservation
+    let mut selected = Vec::new();
+    for target in &targets {
+        match expired.iter().find(|c| c.record_type == target.record_type && c.record_id == target.record_id) {
+            Some(candidate) => selected.push(candidate),
+            None => return Err(format!("{} {} is not past its retention period", target.record_type, target.record_id)),
+        }
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let now = chrono::Local::now().naive_local().to_string();
+    let mut files = Vec::new();
+    for candidate in &selected {
+        tx.execute(&format!("DELETE FROM {} WHERE id = ?1", candidate.record_type), params![candidate.record_id])
+            .map_err(|e| e.to_string())?;
+        delete_record_attachments(&tx, &candidate.record_type, candidate.record_id)?;
+        if candidate.record_type == "admin_documents" {
+            delete_document_versions(&tx, candidate.record_id)?;
+        }
+
+        let details = json!({
+            "label": candidate.label,
+            "category": candidate.category,
+            "rule_id": candidate.rule_id,
+            "reference_date": candidate.reference_date,
+            "retention_end": candidate.retention_end,
+            "files": candidate.files,
+        });
+        tx.execute(
+            "INSERT INTO audit_log (timestamp, action, record_type, record_id, details) VALUES (?1, 'retention_purge', ?2, ?3, ?4)",
+            params![now, candidate.record_type, candidate.record_id, details.to_string()],
+        ).map_err(|e| e.to_string())?;
+        files.extend(candidate.files.iter().cloned());
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    let (deleted_files, failed_files) = remove_unreferenced_files(&conn, files)?;
+    Ok(PurgeSummary { purged: selected.len(), deleted_files, failed_files })
+}
@@ -0,0 +1,230 @@
+// ------------------------------------ Full-text search -----------------------------------------
+//
+// Index FTS5 unique couvrant factures, revenus, documents administratifs et tâches. Chaque ligne
+// indexée a un titre (créancier, source, description du document, titre de la tâche) et un corps
+// (concern, catégorie, description, dates...). Le texte extrait des pièces jointes y est ajouté
+// comme ligne supplémentaire de l'enregistrement. Des triggers gardent l'index à jour.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::command;
+
+const DEFAULT_LIMIT: i64 = 50;
+// Poids bm25 des colonnes (record_type, record_id, attachment_id, title, body) : un mot du titre compte davantage
+const RANK: &str = "bm25(search_index, 0.0, 0.0, 0.0, 5.0, 1.0)";
+
+struct SearchSource {
+    table: &'static str,
+    title: &'static str,
+    body: &'static [&'static str],
+    // La table a une colonne is_draft : ses brouillons sont exclus des résultats par défaut
+    has_drafts: bool,
+}
+
+const SEARCH_SOURCES: &[SearchSource] = &[
+    SearchSource {
+        table: "invoices",
+        title: "creditor",
+        body: &["concern", "category", "description", "reference", "due_date"],
+        has_drafts: true,
+    },
+    SearchSource {
+        table: "revenues",
+        title: "source",
+        body: &["revenue_type", "revenue_description", "receipt_date"],
+        has_drafts: true,
+    },
+    SearchSource {
+        table: "admin_documents",
+        title: "admin_doc_description",
+        body: &["admin_doc_concern", "admin_doc_category", "admin_doc_status", "issue_date", "expiry_date"],
+        has_drafts: true,
+    },
+    SearchSource {
+        table: "tasks",
+        title: "title",
+        body: &["description", "category", "due_date"],
+        has_drafts: false,
+    },
+];
+
+impl SearchSource {
+    // Valeurs à indexer pour la ligne `row` (new/old dans les triggers)
+    fn values(&self, row: &str) -> String {
+        let body: Vec<String> = self.body.iter().map(|c| format!("COALESCE({}.{}, '')", row, c)).collect();
+        format!(
+            "'{}', {row}.id, NULL, COALESCE({row}.{}, ''), {}",
+            self.table,
+            self.title,
+            body.join(" || ' ' || "),
+            row = row
+        )
+    }
+}
+
+#[derive(Serialize)]
+pub struct SearchHit {
+    record_type: String,
+    record_id: i64,
+    // Renseigné quand le texte trouvé vient d'une pièce jointe
+    attachment_id: Option<i64>,
+    title: String,
+    // Extrait du texte trouvé, les termes correspondants entre [ ]
+    snippet: String,
+    rank: f64,
+}
+
+// Crée l'index et ses triggers ; un index tout juste créé est rempli avec les données existantes
+pub(crate) fn create_search_index(conn: &Connection) -> rusqlite::Result<()> {
+    let exists: Option<String> = conn
+        .query_row("SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'search_index'", [], |row| row.get(0))
+        .optional()?;
+    // Une table FTS5 ne peut pas recevoir de nouvelle colonne : un index d'une version précédente est reconstruit
+    let exists = match exists {
+        Some(_) if !index_has_column(conn, "attachment_id")? => {
+            conn.execute("DROP TABLE search_index", [])?;
+            None
+        }
+        exists => exists,
+    };
+    conn.execute(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5(
+            record_type UNINDEXED,
+            record_id UNINDEXED,
+            attachment_id UNINDEXED,
+            title,
+            body,
+            tokenize = 'unicode61 remove_diacritics 2'
+        )",
+        [],
+    )?;
+
+    for source in SEARCH_SOURCES {
+        // Recréés à chaque démarrage pour suivre l'évolution des colonnes indexées
+        conn.execute_batch(&format!(
+            "DROP TRIGGER IF EXISTS {table}_search_insert;
+             DROP TRIGGER IF EXISTS {table}_search_update;
+             DROP TRIGGER IF EXISTS {table}_search_delete;
+             CREATE TRIGGER {table}_search_insert AFTER INSERT ON {table} BEGIN {insert} END;
+             CREATE TRIGGER {table}_search_update AFTER UPDATE ON {table} BEGIN {delete} {insert} END;
+             CREATE TRIGGER {table}_search_delete AFTER DELETE ON {table} BEGIN {delete} END;",
+            table = source.table,
+            insert = format!("INSERT INTO search_index (record_type, record_id, attachment_id, title, body) VALUES ({});", source.values("new")),
+            delete = format!(
+                "DELETE FROM search_index WHERE record_type = '{}' AND record_id = old.id AND attachment_id IS NULL;",
+                source.table
+            )
+        ))?;
+
+        if exists.is_none() {
+            conn.execute(
+                &format!(
+                    "INSERT INTO search_index (record_type, record_id, attachment_id, title, body) SELECT {} FROM {} r",
+                    source.values("r"),
+                    source.table
+                ),
+                [],
+            )?;
+        }
+    }
+
+    // Texte extrait des pièces jointes
+    let insert_text = "INSERT INTO search_index (record_type, record_id, attachment_id, title, body)
+        SELECT new.record_type, new.record_id, new.id, new.original_name, new.extracted_text
+        WHERE new.extracted_text IS NOT NULL AND new.extracted_text != '';";
+    let delete_text = "DELETE FROM search_index WHERE attachment_id = old.id;";
+    conn.execute_batch(&format!(
+        "DROP TRIGGER IF EXISTS attachments_search_insert;
+         DROP TRIGGER IF EXISTS attachments_search_update;
+         DROP TRIGGER IF EXISTS attachments_search_delete;
+         CREATE TRIGGER attachments_search_insert AFTER INSERT ON attachments BEGIN {insert} END;
+         CREATE TRIGGER attachments_search_update AFTER UPDATE OF record_type, record_id, original_name, extracted_text ON attachments BEGIN {delete} {insert} END;
+         CREATE TRIGGER attachments_search_delete AFTER DELETE ON attachments BEGIN {delete} END;",
+        insert = insert_text,
+        delete = delete_text
+    ))?;
+    if exists.is_none() {
+        conn.execute(
+            "INSERT INTO search_index (record_type, record_id, attachment_id, title, body)
+             SELECT record_type, record_id, id, original_name, extracted_text FROM attachments
+             WHERE extracted_text IS NOT NULL AND extracted_text != ''",
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+fn index_has_column(conn: &Connection, column: &str) -> rusqlite::Result<bool> {
+    let mut stmt = conn.prepare("PRAGMA table_info(search_index)")?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?.collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(columns.iter().any(|c| c == column))
+}
+
+// Transforme la saisie en requête FTS5 : chaque mot est cherché comme préfixe, tous doivent être présents
+fn fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| format!("\"{}\"*", t))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+#[command]
+pub fn search(query: String, record_types: Option<Vec<String>>, limit: Option<i64>, include_drafts: Option<bool>) -> Result<Vec<SearchHit>, String> {
+    let fts_query = match fts_query(&query) {
+        Some(fts_query) => fts_query,
+        None => return Ok(Vec::new()),
+    };
+    if let Some(unknown) = record_types.iter().flatten().find(|t| !SEARCH_SOURCES.iter().any(|s| s.table == t.as_str())) {
+        return Err(format!("Unsupported record type: {}", unknown));
+    }
+
+    // Les brouillons restent indexés (leur approbation ne demande pas de réindexer) mais sont filtrés ici
+    let draft_filter: String = if include_drafts.unwrap_or(false) {
+        String::new()
+    } else {
+        SEARCH_SOURCES
+            .iter()
+            .filter(|s| s.has_drafts)
+            .map(|s| format!(" AND NOT (record_type = '{table}' AND record_id IN (SELECT id FROM {table} WHERE is_draft = 1))", table = s.table))
+            .collect()
+    };
+
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT record_type, record_id, attachment_id, title, snippet(search_index, -1, '[', ']', '…', 12), {rank}
+         FROM search_index
+         WHERE search_index MATCH ?1{drafts}
+         ORDER BY {rank}",
+        rank = RANK,
+        drafts = draft_filter
+    )).map_err(|e| e.to_string())?;
+    let hit_iter = stmt.query_map(params![fts_query], |row| {
+        Ok(SearchHit {
+            record_type: row.get(0)?,
+            record_id: row.get(1)?,
+            attachment_id: row.get(2)?,
+            title: row.get(3)?,
+            snippet: row.get(4)?,
+            rank: row.get(5)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).max(0) as usize;
+    let mut hits = Vec::new();
+    for hit in hit_iter {
+        let hit = hit.map_err(|e| e.to_string())?;
+        if record_types.as_ref().is_none_or(|types| types.contains(&hit.record_type)) {
+            hits.push(hit);
+            if hits.len() >= limit {
+                break;
+            }
+        }
+    }
+    Ok(hits)
+}
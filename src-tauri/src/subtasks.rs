@@ -0,0 +1,282 @@
+// ------------------------------------ Subtasks and checklists -----------------------------------------
+//
+// Une tâche peut avoir des sous-tâches (sur autant de niveaux que nécessaire) et une liste de
+// points à cocher. L'avancement d'une tâche est la moyenne de ses points cochés et de l'avancement
+// de ses sous-tâches ; une tâche terminée est à 100 %.
+
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::command;
+
+use crate::dependencies::mark_blocked;
+use crate::{done_statuses, get_task, task_from_row, DoneStatuses, Task, TASK_COLUMNS};
+
+#[derive(Serialize)]
+pub struct ChecklistItem {
+    id: i64,
+    task_id: i64,
+    label: String,
+    done: bool,
+    position: i64,
+}
+
+#[derive(Serialize)]
+pub struct TaskNode {
+    #[serde(flatten)]
+    task: Task,
+    depth: usize,
+    progress: u8,
+    checklist: Vec<ChecklistItem>,
+    children: Vec<TaskNode>,
+}
+
+// Vue aplatie de l'arbre (ordre de lecture : chaque tâche suivie de ses sous-tâches)
+#[derive(Serialize)]
+pub struct FlatTask {
+    #[serde(flatten)]
+    task: Task,
+    depth: usize,
+    progress: u8,
+    checklist: Vec<ChecklistItem>,
+    child_count: usize,
+}
+
+fn checklist_item_from_row(row: &rusqlite::Row) -> rusqlite::Result<ChecklistItem> {
+    Ok(ChecklistItem {
+        id: row.get(0)?,
+        task_id: row.get(1)?,
+        label: row.get(2)?,
+        done: row.get(3)?,
+        position: row.get(4)?,
+    })
+}
+
+fn query_checklist(conn: &Connection, filter: &str, params: &[&dyn rusqlite::ToSql]) -> Result<Vec<ChecklistItem>, String> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, task_id, label, done, position FROM task_checklist_items {} ORDER BY position, id",
+        filter
+    )).map_err(|e| e.to_string())?;
+    let item_iter = stmt.query_map(params, checklist_item_from_row).map_err(|e| e.to_string())?;
+
+    let mut items = Vec::new();
+    for item in item_iter {
+        items.push(item.map_err(|e| e.to_string())?);
+    }
+    Ok(items)
+}
+
+fn checklist_task_id(conn: &Connection, item_id: i64) -> Result<i64, String> {
+    conn.query_row("SELECT task_id FROM task_checklist_items WHERE id = ?1", params![item_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Checklist item {} not found", item_id))
+}
+
+// Ids de toutes les sous-tâches d'une tâche, à toutes les profondeurs
+pub(crate) fn descendant_ids(conn: &Connection, task_id: i64) -> Result<Vec<i64>, String> {
+    // UNION (et non UNION ALL) arrête la récursion même si la base contenait un cycle
+    let mut stmt = conn.prepare(
+        "WITH RECURSIVE descendants(id) AS (
+            SELECT id FROM tasks WHERE parent_id = ?1
+            UNION SELECT t.id FROM tasks t JOIN descendants d ON t.parent_id = d.id
+        ) SELECT id FROM descendants"
+    ).map_err(|e| e.to_string())?;
+    let ids = stmt
+        .query_map(params![task_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<i64>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(ids)
+}
+
+fn progress(task: &Task, checklist: &[ChecklistItem], children: &[TaskNode], done: &DoneStatuses) -> u8 {
+    if done.contains(&task.status) {
+        return 100;
+    }
+    let parts = checklist.len() + children.len();
+    if parts == 0 {
+        return 0;
+    }
+    let total = checklist.iter().filter(|item| item.done).count() as f64 * 100.0
+        + children.iter().map(|child| child.progress as f64).sum::<f64>();
+    (total / parts as f64).round() as u8
+}
+
+fn build_node(
+    task: Task,
+    depth: usize,
+    children_of: &mut HashMap<Option<i64>, Vec<Task>>,
+    checklists: &mut HashMap<i64, Vec<ChecklistItem>>,
+    done: &DoneStatuses
+) -> TaskNode {
+    let id = task.id as i64;
+    let children: Vec<TaskNode> = children_of
+        .remove(&Some(id))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|child| build_node(child, depth + 1, children_of, checklists, done))
+        .collect();
+    let checklist = checklists.remove(&id).unwrap_or_default();
+    TaskNode { progress: progress(&task, &checklist, &children, done), task, depth, checklist, children }
+}
+
+// Arbre complet des tâches, ou sous-arbre d'une tâche
+fn task_tree(conn: &Connection, root_id: Option<i64>) -> Result<Vec<TaskNode>, String> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM tasks ORDER BY id", TASK_COLUMNS)).map_err(|e| e.to_string())?;
+    let mut tasks = stmt
+        .query_map([], task_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<Task>>>()
+        .map_err(|e| e.to_string())?;
+    mark_blocked(conn, &mut tasks)?;
+    let done = done_statuses(conn)?;
+
+    let mut checklists: HashMap<i64, Vec<ChecklistItem>> = HashMap::new();
+    for item in query_checklist(conn, "", &[])? {
+        checklists.entry(item.task_id).or_default().push(item);
+    }
+
+    // Une tâche dont le parent n'existe plus est traitée comme une tâche racine
+    let ids: Vec<i64> = tasks.iter().map(|t| t.id as i64).collect();
+    let mut children_of: HashMap<Option<i64>, Vec<Task>> = HashMap::new();
+    let mut root = None;
+    for task in tasks {
+        if Some(task.id as i64) == root_id {
+            root = Some(task);
+            continue;
+        }
+        let parent = task.parent_id.filter(|p| ids.contains(p));
+        children_of.entry(parent).or_default().push(task);
+    }
+
+    match root_id {
+        Some(root_id) => {
+            let root = root.ok_or_else(|| format!("Task {} not found", root_id))?;
+            Ok(vec![build_node(root, 0, &mut children_of, &mut checklists, &done)])
+        }
+        None => {
+            let roots = children_of.remove(&None).unwrap_or_default();
+            Ok(roots.into_iter().map(|task| build_node(task, 0, &mut children_of, &mut checklists, &done)).collect())
+        }
+    }
+}
+
+fn flatten(node: TaskNode, flat: &mut Vec<FlatTask>) {
+    let child_count = node.children.len();
+    flat.push(FlatTask {
+        task: node.task,
+        depth: node.depth,
+        progress: node.progress,
+        checklist: node.checklist,
+        child_count,
+    });
+    for child in node.children {
+        flatten(child, flat);
+    }
+}
+
+// Rattache une tâche à une autre (ou la remet à la racine avec None)
+#[command]
+pub fn set_task_parent(id: i64, parent_id: Option<i64>) -> Result<Task, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    get_task(&conn, id as i32)?;
+    if let Some(parent_id) = parent_id {
+        get_task(&conn, parent_id as i32)?;
+        if parent_id == id || descendant_ids(&conn, id)?.contains(&parent_id) {
+            return Err("A task cannot be moved under itself or one of its subtasks".to_string());
+        }
+    }
+
+    let current_date = chrono::Local::now().naive_local().to_string();
+    conn.execute(
+        "UPDATE tasks SET parent_id = ?1, updated_at = ?2 WHERE id = ?3",
+        params![parent_id, current_date, id],
+    ).map_err(|e| e.to_string())?;
+    get_task(&conn, id as i32)
+}
+
+#[command]
+pub fn get_task_tree(root_id: Option<i64>) -> Result<Vec<TaskNode>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    task_tree(&conn, root_id)
+}
+
+#[command]
+pub fn get_task_tree_flat(root_id: Option<i64>) -> Result<Vec<FlatTask>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let mut flat = Vec::new();
+    for node in task_tree(&conn, root_id)? {
+        flatten(node, &mut flat);
+    }
+    Ok(flat)
+}
+
+#[command]
+pub fn get_checklist(task_id: i64) -> Result<Vec<ChecklistItem>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    query_checklist(&conn, "WHERE task_id = ?1", &[&task_id])
+}
+
+#[command]
+pub fn add_checklist_item(task_id: i64, label: String) -> Result<Vec<ChecklistItem>, String> {
+    let label = label.trim().to_string();
+    if label.is_empty() {
+        return Err("The checklist item cannot be empty".to_string());
+    }
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    get_task(&conn, task_id as i32)?;
+    conn.execute(
+        "INSERT INTO task_checklist_items (task_id, label, done, position)
+         VALUES (?1, ?2, 0, (SELECT COALESCE(MAX(position) + 1, 0) FROM task_checklist_items WHERE task_id = ?1))",
+        params![task_id, label],
+    ).map_err(|e| e.to_string())?;
+    get_checklist(task_id)
+}
+
+// Mise à jour partielle : libellé et/ou état coché
+#[command]
+pub fn update_checklist_item(id: i64, label: Option<String>, done: Option<bool>) -> Result<Vec<ChecklistItem>, String> {
+    let label = label.map(|l| l.trim().to_string());
+    if label.as_deref().is_some_and(str::is_empty) {
+        return Err("The checklist item cannot be empty".to_string());
+    }
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let task_id = checklist_task_id(&conn, id)?;
+    conn.execute(
+        "UPDATE task_checklist_items SET label = COALESCE(?1, label), done = COALESCE(?2, done) WHERE id = ?3",
+        params![label, done, id],
+    ).map_err(|e| e.to_string())?;
+    get_checklist(task_id)
+}
+
+#[command]
+pub fn delete_checklist_item(id: i64) -> Result<Vec<ChecklistItem>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let task_id = checklist_task_id(&conn, id)?;
+    conn.execute(
+        "DELETE FROM task_checklist_items WHERE id = ?1",
+        params![id],
+    ).map_err(|e| e.to_string())?;
+    get_checklist(task_id)
+}
+
+#[command]
+pub fn reorder_checklist_items(task_id: i64, item_ids: Vec<i64>) -> Result<Vec<ChecklistItem>, String> {
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    for (position, id) in item_ids.iter().enumerate() {
+        let updated = tx.execute(
+            "UPDATE task_checklist_items SET position = ?1 WHERE id = ?2 AND task_id = ?3",
+            params![position as i64, id, task_id],
+        ).map_err(|e| e.to_string())?;
+        if updated == 0 {
+            return Err(format!("Checklist item {} does not belong to task {}", id, task_id));
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_checklist(task_id)
+}
@@ -0,0 +1,316 @@
+// ------------------------------------ Invoice field suggestions -----------------------------------------
+//
+// Propose montant, échéance, IBAN, référence et créancier à partir du texte d'une facture PDF.
+// Le créancier est reconnu par son IBAN ou son nom ; ses règles d'extraction (expressions
+// régulières) priment sur les heuristiques générales. Rien n'est enregistré : le formulaire
+// d'ajout reprend les champs proposés.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use chrono::NaiveDate;
+use regex::Regex;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::command;
+
+use crate::extraction::extract_text;
+use crate::vault::resolve_file_reference;
+
+const RULE_FIELDS: &[&str] = &["amount", "due_date", "iban", "reference"];
+const DATE_FORMATS: &[&str] = &["%d.%m.%Y", "%d/%m/%Y", "%d-%m-%Y", "%Y-%m-%d", "%d.%m.%y", "%d/%m/%y"];
+
+const AMOUNT: &str = r"(\d{1,3}(?:['’ .,]?\d{3})*[.,]\d{2})\b";
+const DATE: &str = r"(\d{1,2}[./-]\d{1,2}[./-]\d{2,4}|\d{4}-\d{2}-\d{2})";
+
+// Expressions compilées une seule fois, au premier texte analysé
+static IBAN_PATTERN: LazyLock<Regex> = LazyLock::new(|| regex(r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,3})?\b"));
+static KEYWORD_AMOUNT_PATTERN: LazyLock<Regex> = LazyLock::new(|| regex(&format!(
+    r"(?i)(?:total|montant|betrag|amount|à payer|a payer|zu zahlen|importo|somme)[^0-9\n]{{0,30}}?{}",
+    AMOUNT
+)));
+static CURRENCY_AMOUNT_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| regex(&format!(r"(?i)(?:CHF|EUR|Fr\.|€)\s?{a}|{a}\s?(?:CHF|EUR|€)", a = AMOUNT)));
+static DUE_DATE_PATTERN: LazyLock<Regex> = LazyLock::new(|| regex(&format!(
+    r"(?i)(?:échéance|echeance|payable (?:jusqu'au|avant le|le)|due date|due on|payment due|fällig(?: am)?|zahlbar bis|scadenza)[^0-9\n]{{0,20}}?{}",
+    DATE
+)));
+static QR_REFERENCE_PATTERN: LazyLock<Regex> = LazyLock::new(|| regex(r"\b\d{2}(?: ?\d{5}){5}\b"));
+static CREDITOR_REFERENCE_PATTERN: LazyLock<Regex> = LazyLock::new(|| regex(r"\bRF\d{2}(?: ?[A-Z0-9]{1,4}){1,6}\b"));
+static KEYWORD_REFERENCE_PATTERN: LazyLock<Regex> = LazyLock::new(|| regex(
+    r"(?i)(?:référence|reference|referenz|réf\.|ref\.|invoice (?:no|number)|facture (?:n°|no)|rechnung(?:s-?nr\.?| nr\.?))[\s:.#n°]*([A-Z0-9][A-Z0-9\-/]{3,})"
+));
+
+#[derive(Serialize)]
+pub struct ExtractionRule {
+    id: i64,
+    creditor: String,
+    field: String,
+    pattern: String,
+    date_format: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct InvoiceSuggestion {
+    pub(crate) creditor: Option<String>,
+    pub(crate) amount: Option<f64>,
+    pub(crate) due_date: Option<String>,
+    iban: Option<String>,
+    pub(crate) reference: Option<String>,
+    // Origine de chaque champ proposé : "rule", "iban", "name" ou "heuristic"
+    sources: BTreeMap<String, String>,
+}
+
+fn regex(pattern: &str) -> Regex {
+    Regex::new(pattern).expect("invalid built-in pattern")
+}
+
+// "1'234.50", "1 234,50", "1.234,50" → 1234.5
+fn parse_amount(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let decimal = value.rfind(['.', ',']).filter(|i| value.len() - i == 3);
+    let (integer, fraction) = match decimal {
+        Some(i) => (&value[..i], &value[i + 1..]),
+        None => (value, "0"),
+    };
+    let integer: String = integer.chars().filter(char::is_ascii_digit).collect();
+    format!("{}.{}", integer, fraction).parse().ok()
+}
+
+fn parse_date(value: &str, format: Option<&str>) -> Option<String> {
+    let formats = match format {
+        Some(format) => vec![format],
+        None => DATE_FORMATS.to_vec(),
+    };
+    formats
+        .iter()
+        .find_map(|f| NaiveDate::parse_from_str(value.trim(), f).ok())
+        .map(|d| d.to_string())
+}
+
+pub(crate) fn normalize_iban(value: &str) -> String {
+    value.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_uppercase()
+}
+
+// Contrôle modulo 97 (ISO 13616)
+pub(crate) fn is_valid_iban(iban: &str) -> bool {
+    if iban.len() < 15 || iban.len() > 34 || !iban.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return false;
+    }
+    let rearranged = format!("{}{}", &iban[4..], &iban[..4]);
+    let mut remainder = 0u32;
+    for c in rearranged.chars() {
+        let digits = c.to_digit(36).unwrap_or(0);
+        remainder = if digits >= 10 { (remainder * 100 + digits) % 97 } else { (remainder * 10 + digits) % 97 };
+    }
+    remainder == 1
+}
+
+fn find_iban(text: &str) -> Option<String> {
+    IBAN_PATTERN
+        .find_iter(text)
+        .map(|m| normalize_iban(m.as_str()))
+        .find(|iban| is_valid_iban(iban))
+}
+
+// Le plus grand montant annoncé par un mot-clé (le total plutôt qu'un sous-total), sinon le plus grand montant en devise
+fn find_amount(text: &str) -> Option<f64> {
+    let largest = |re: &Regex| {
+        re.captures_iter(text)
+            .filter_map(|c| c.get(1).or_else(|| c.get(2)).and_then(|m| parse_amount(m.as_str())))
+            .fold(None, |max: Option<f64>, amount| Some(max.map_or(amount, |m| m.max(amount))))
+    };
+    largest(&KEYWORD_AMOUNT_PATTERN).or_else(|| largest(&CURRENCY_AMOUNT_PATTERN))
+}
+
+fn find_due_date(text: &str) -> Option<String> {
+    DUE_DATE_PATTERN
+        .captures_iter(text)
+        .find_map(|c| parse_date(&c[1], None))
+}
+
+// Référence QR (27 chiffres), référence créancier ISO 11649 (RF..), sinon numéro annoncé par un mot-clé
+fn find_reference(text: &str) -> Option<String> {
+    if let Some(m) = QR_REFERENCE_PATTERN.find(text).or_else(|| CREDITOR_REFERENCE_PATTERN.find(text)) {
+        return Some(normalize_iban(m.as_str()));
+    }
+    KEYWORD_REFERENCE_PATTERN
+        .captures(text)
+        .map(|c| c[1].to_string())
+}
+
+fn rule_value(rule: &ExtractionRule, text: &str) -> Result<Option<String>, String> {
+    let re = Regex::new(&rule.pattern).map_err(|e| format!("Rule {}: {}", rule.id, e))?;
+    Ok(re.captures(text).and_then(|c| {
+        c.name("value").or_else(|| c.get(1)).or_else(|| c.get(0)).map(|m| m.as_str().to_string())
+    }))
+}
+
+fn query_rules(conn: &Connection, creditor: Option<&str>) -> Result<Vec<ExtractionRule>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, creditor, field, pattern, date_format FROM invoice_extraction_rules
+         WHERE ?1 IS NULL OR creditor = ?1 COLLATE NOCASE ORDER BY creditor, field, id"
+    ).map_err(|e| e.to_string())?;
+    let rule_iter = stmt.query_map(params![creditor], |row| {
+        Ok(ExtractionRule {
+            id: row.get(0)?,
+            creditor: row.get(1)?,
+            field: row.get(2)?,
+            pattern: row.get(3)?,
+            date_format: row.get(4)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut rules = Vec::new();
+    for rule in rule_iter {
+        rules.push(rule.map_err(|e| e.to_string())?);
+    }
+    Ok(rules)
+}
+
+fn match_creditor(conn: &Connection, text: &str, iban: Option<&str>) -> Result<Option<(String, &'static str)>, String> {
+    let mut stmt = conn.prepare("SELECT name, iban FROM creditors").map_err(|e| e.to_string())?;
+    let creditors: Vec<(String, Option<String>)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<_>>()
+        .map_err(|e| e.to_string())?;
+
+    if let Some(iban) = iban {
+        if let Some((name, _)) = creditors.iter().find(|(_, known)| known.as_deref().map(normalize_iban).as_deref() == Some(iban)) {
+            return Ok(Some((name.clone(), "iban")));
+        }
+    }
+    // Nom le plus long présent dans le texte ("Swisscom Directories" plutôt que "Swisscom")
+    let lowercase = text.to_lowercase();
+    Ok(creditors
+        .into_iter()
+        .map(|(name, _)| name)
+        .filter(|name| name.trim().len() >= 3 && lowercase.contains(&name.trim().to_lowercase()))
+        .max_by_key(|name| name.len())
+        .map(|name| (name, "name")))
+}
+
+pub(crate) fn suggest_from_text(conn: &Connection, text: &str) -> Result<InvoiceSuggestion, String> {
+    let mut suggestion = InvoiceSuggestion::default();
+    let heuristic = |sources: &mut BTreeMap<String, String>, field: &str, found: bool| {
+        if found {
+            sources.insert(field.to_string(), "heuristic".to_string());
+        }
+    };
+
+    suggestion.iban = find_iban(text);
+    heuristic(&mut suggestion.sources, "iban", suggestion.iban.is_some());
+    suggestion.amount = find_amount(text);
+    heuristic(&mut suggestion.sources, "amount", suggestion.amount.is_some());
+    suggestion.due_date = find_due_date(text);
+    heuristic(&mut suggestion.sources, "due_date", suggestion.due_date.is_some());
+    suggestion.reference = find_reference(text);
+    heuristic(&mut suggestion.sources, "reference", suggestion.reference.is_some());
+
+    let creditor = match match_creditor(conn, text, suggestion.iban.as_deref())? {
+        Some((creditor, source)) => {
+            suggestion.sources.insert("creditor".to_string(), source.to_string());
+            creditor
+        }
+        None => return Ok(suggestion),
+    };
+
+    for rule in query_rules(conn, Some(&creditor))? {
+        let value = match rule_value(&rule, text)? {
+            Some(value) => value,
+            None => continue,
+        };
+        let applied = match rule.field.as_str() {
+            "amount" => parse_amount(&value).map(|amount| suggestion.amount = Some(amount)),
+            "due_date" => parse_date(&value, rule.date_format.as_deref()).map(|date| suggestion.due_date = Some(date)),
+            "iban" => Some(normalize_iban(&value)).filter(|iban| is_valid_iban(iban)).map(|iban| suggestion.iban = Some(iban)),
+            _ => {
+                suggestion.reference = Some(value.trim().to_string());
+                Some(())
+            }
+        };
+        if applied.is_some() {
+            suggestion.sources.insert(rule.field.clone(), "rule".to_string());
+        }
+    }
+    suggestion.creditor = Some(creditor);
+    Ok(suggestion)
+}
+
+#[command]
+pub fn suggest_invoice_fields(path: String) -> Result<InvoiceSuggestion, String> {
+    let file = resolve_file_reference(&path)?;
+    let mime_type = mime_guess::from_path(&file).first_or_octet_stream().to_string();
+    if mime_type != "application/pdf" && !mime_type.starts_with("text/") {
+        return Err(format!("Cannot read text from {}", Path::new(&path).display()));
+    }
+    let text = extract_text(&file, &mime_type)?;
+
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    suggest_from_text(&conn, &text)
+}
+
+#[command]
+pub fn add_extraction_rule(creditor: String, field: String, pattern: String, date_format: Option<String>) -> Result<(), String> {
+    if !RULE_FIELDS.contains(&field.as_str()) {
+        return Err(format!("Unknown field '{}', expected one of {}", field, RULE_FIELDS.join(", ")));
+    }
+    Regex::new(&pattern).map_err(|e| e.to_string())?;
+
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO invoice_extraction_rules (creditor, field, pattern, date_format) VALUES (?1, ?2, ?3, ?4)",
+        params![creditor, field, pattern, date_format],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[command]
+pub fn get_extraction_rules(creditor: Option<String>) -> Result<Vec<ExtractionRule>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    query_rules(&conn, creditor.as_deref())
+}
+
+#[command]
+pub fn delete_extraction_rule(id: i64) -> Result<(), String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM invoice_extraction_rules WHERE id = ?1",
+        params![id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iban_checksum() {
+        assert!(is_valid_iban("CH9300762011623852957"));
+        assert!(is_valid_iban("DE89370400440532013000"));
+        assert!(is_valid_iban("GB82WEST12345698765432"));
+        // Un chiffre modifié ou deux chiffres inversés
+        assert!(!is_valid_iban("CH9300762011623852958"));
+        assert!(!is_valid_iban("DE89370400440532031000"));
+        // Longueur ou caractères hors norme
+        assert!(!is_valid_iban("CH93007620116"));
+        assert!(!is_valid_iban("CH93-0076-2011-6238-5295-7"));
+    }
+
+    #[test]
+    fn iban_is_normalized_before_the_check() {
+        let iban = normalize_iban("ch93 0076 2011 6238 5295 7");
+        assert_eq!(iban, "CH9300762011623852957");
+        assert!(is_valid_iban(&iban));
+    }
+
+    #[test]
+    fn first_valid_iban_in_text() {
+        let text = "Compte CH93 0076 2011 6238 5295 8 (ancien)\nIBAN: CH93 0076 2011 6238 5295 7";
+        assert_eq!(find_iban(text).as_deref(), Some("CH9300762011623852957"));
+        assert_eq!(find_iban("Aucun compte indiqué"), None);
+    }
+}
@@ -0,0 +1,119 @@
+// ------------------------------------ Document vault -----------------------------------------
+//
+// Les pièces jointes sont copiées (ou déplacées) dans un dossier géré par l'application, rangées par
+// type d'enregistrement et par année, et nommées par le hash de leur contenu : un même fichier
+// n'est stocké qu'une fois. L'enregistrement garde une référence "vault://<type>/<année>/<hash>.<ext>"
+// au lieu d'un chemin absolu qui casse dès que le fichier d'origine est déplacé.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use chrono::Datelike;
+use sha2::{Digest, Sha256};
+
+use crate::hash_file;
+
+pub(crate) const VAULT_DIR: &str = "vault";
+pub(crate) const VAULT_PREFIX: &str = "vault://";
+
+// Colonnes contenant des chemins de fichiers, avec la date qui sert à les ranger dans le coffre ;
+// les pièces jointes des tâches sont une liste séparée par des ';'
+pub(crate) const FILE_COLUMNS: &[(&str, &str, bool, Option<&str>)] = &[
+    ("invoices", "path", false, Some("due_date")),
+    ("revenues", "revenue_path", false, Some("receipt_date")),
+    ("admin_documents", "admin_doc_path", false, None),
+    ("tasks", "attachments", true, Some("due_date")),
+];
+
+pub(crate) fn split_paths(value: &str, multi: bool) -> Vec<&str> {
+    if multi {
+        value.split(';').map(str::trim).filter(|p| !p.is_empty()).collect()
+    } else if value.trim().is_empty() {
+        Vec::new()
+    } else {
+        vec![value]
+    }
+}
+
+pub(crate) fn file_name_of(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "file".to_string())
+}
+
+pub(crate) fn is_vault_reference(reference: &str) -> bool {
+    reference.starts_with(VAULT_PREFIX)
+}
+
+// Année de rangement : celle de la date de l'enregistrement si elle est lisible, sinon l'année en cours
+pub(crate) fn vault_year(date: Option<&str>) -> i32 {
+    date.and_then(|d| d.get(0..4))
+        .and_then(|y| y.parse().ok())
+        .unwrap_or_else(|| chrono::Local::now().year())
+}
+
+// Chemin sur le disque d'une référence de fichier (référence du coffre ou chemin classique)
+pub(crate) fn resolve_file_reference(reference: &str) -> Result<PathBuf, String> {
+    match reference.strip_prefix(VAULT_PREFIX) {
+        Some(relative) => {
+            let relative = Path::new(relative);
+            if relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+                return Err(format!("Invalid vault reference: {}", reference));
+            }
+            Ok(Path::new(VAULT_DIR).join(relative))
+        }
+        None => Ok(PathBuf::from(reference)),
+    }
+}
+
+// Copie `source` dans le coffre, ou l'y déplace si `move_file` est vrai, et renvoie sa référence
+pub(crate) fn store_in_vault(source: &str, record_type: &str, year: i32, move_file: bool) -> Result<String, String> {
+    if is_vault_reference(source) {
+        return Ok(source.to_string());
+    }
+
+    let sha256 = hash_file(source)?;
+    let file_name = match Path::new(source).extension() {
+        Some(extension) => format!("{}.{}", sha256, extension.to_string_lossy().to_lowercase()),
+        None => sha256.clone(),
+    };
+    let relative = format!("{}/{}/{}", record_type, year, file_name);
+    let destination = Path::new(VAULT_DIR).join(&relative);
+
+    // Contenu déjà présent dans le coffre : rien à copier
+    if !destination.is_file() {
+        fs::create_dir_all(destination.parent().unwrap_or(Path::new(VAULT_DIR))).map_err(|e| e.to_string())?;
+        if move_file {
+            // rename échoue entre deux volumes différents, on retombe alors sur une copie
+            if fs::rename(source, &destination).is_err() {
+                fs::copy(source, &destination).map_err(|e| e.to_string())?;
+            }
+        } else {
+            fs::copy(source, &destination).map_err(|e| e.to_string())?;
+        }
+    }
+    if move_file && Path::new(source).exists() {
+        fs::remove_file(source).map_err(|e| e.to_string())?;
+    }
+
+    Ok(format!("{}{}", VAULT_PREFIX, relative))
+}
+
+// Range un contenu reçu en mémoire (pièce jointe d'un e-mail) sous le nom de fichier `file_name`
+pub(crate) fn store_bytes_in_vault(content: &[u8], file_name: &str, record_type: &str, year: i32) -> Result<String, String> {
+    let sha256 = format!("{:x}", Sha256::digest(content));
+    let file_name = match Path::new(file_name).extension() {
+        Some(extension) => format!("{}.{}", sha256, extension.to_string_lossy().to_lowercase()),
+        None => sha256,
+    };
+    let relative = format!("{}/{}/{}", record_type, year, file_name);
+    let destination = Path::new(VAULT_DIR).join(&relative);
+
+    if !destination.is_file() {
+        fs::create_dir_all(destination.parent().unwrap_or(Path::new(VAULT_DIR))).map_err(|e| e.to_string())?;
+        fs::write(&destination, content).map_err(|e| e.to_string())?;
+    }
+
+    Ok(format!("{}{}", VAULT_PREFIX, relative))
+}
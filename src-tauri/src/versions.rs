@@ -0,0 +1,141 @@
+// ------------------------------------ Admin document versions -----------------------------------------
+//
+// Un document administratif garde l'historique de ses fichiers (contrat renouvelé, nouveau relevé).
+// La version courante est la première pièce jointe du document, donc celle que l'on ouvre et que
+// l'on retrouve par la recherche ; restaurer une ancienne version la remet simplement en place.
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::command;
+
+use crate::attachments::replace_primary_attachment;
+use crate::vault::{file_name_of, store_in_vault, vault_year};
+
+#[derive(Serialize)]
+pub struct AdminDocumentVersion {
+    id: i64,
+    admin_document_id: i64,
+    version: i64,
+    file_reference: String,
+    original_name: String,
+    note: Option<String>,
+    uploaded_date: String,
+    current: bool,
+}
+
+// Enregistre une version (le fichier est déjà rangé dans le coffre) et renvoie son id
+pub(crate) fn insert_version(
+    conn: &Connection,
+    admin_document_id: i64,
+    file_reference: &str,
+    original_name: &str,
+    note: Option<&str>
+) -> Result<i64, String> {
+    let uploaded_date = chrono::Local::now().naive_local().to_string();
+    conn.execute(
+        "INSERT INTO admin_document_versions (admin_document_id, version, file_reference, original_name, note, uploaded_date)
+         VALUES (?1, (SELECT COALESCE(MAX(version) + 1, 1) FROM admin_document_versions WHERE admin_document_id = ?1), ?2, ?3, ?4, ?5)",
+        params![admin_document_id, file_reference, original_name, note, uploaded_date],
+    ).map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
+// Les documents enregistrés avant l'historique reçoivent leur fichier actuel comme version 1
+pub(crate) fn migrate_document_versions(conn: &Connection) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT d.id, d.admin_doc_path, COALESCE(d.received_at, '') FROM admin_documents d
+         WHERE d.admin_doc_path != '' AND NOT EXISTS (SELECT 1 FROM admin_document_versions v WHERE v.admin_document_id = d.id)"
+    )?;
+    let documents: Vec<(i64, String, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    for (id, reference, received_at) in documents {
+        conn.execute(
+            "INSERT INTO admin_document_versions (admin_document_id, version, file_reference, original_name, note, uploaded_date)
+             VALUES (?1, 1, ?2, ?3, NULL, ?4)",
+            params![id, reference, file_name_of(&reference), received_at],
+        )?;
+    }
+    Ok(())
+}
+
+pub(crate) fn delete_document_versions(conn: &Connection, admin_document_id: i64) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM admin_document_versions WHERE admin_document_id = ?1",
+        params![admin_document_id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn current_reference(conn: &Connection, admin_document_id: i64) -> Result<String, String> {
+    conn.query_row(
+        "SELECT admin_doc_path FROM admin_documents WHERE id = ?1",
+        params![admin_document_id],
+        |row| row.get(0),
+    ).map_err(|_| format!("Admin document {} not found", admin_document_id))
+}
+
+#[command]
+pub fn add_admin_doc_version(admin_document_id: i64, path: String, note: Option<String>, move_file: Option<bool>) -> Result<Vec<AdminDocumentVersion>, String> {
+    let mut conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    current_reference(&conn, admin_document_id)?;
+
+    let reference = store_in_vault(&path, "admin_documents", vault_year(None), move_file.unwrap_or(false))?;
+    let original_name = file_name_of(&path);
+    let added_date = chrono::Local::now().naive_local().date().to_string();
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    insert_version(&tx, admin_document_id, &reference, &original_name, note.as_deref())?;
+    replace_primary_attachment(&tx, "admin_documents", admin_document_id, &reference, &original_name, &added_date)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_admin_doc_versions(admin_document_id)
+}
+
+// Versions du document, la plus récente en premier
+#[command]
+pub fn get_admin_doc_versions(admin_document_id: i64) -> Result<Vec<AdminDocumentVersion>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let current = current_reference(&conn, admin_document_id)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, admin_document_id, version, file_reference, original_name, note, uploaded_date
+         FROM admin_document_versions WHERE admin_document_id = ?1 ORDER BY version DESC"
+    ).map_err(|e| e.to_string())?;
+    let version_iter = stmt.query_map(params![admin_document_id], |row| {
+        let file_reference: String = row.get(3)?;
+        Ok(AdminDocumentVersion {
+            id: row.get(0)?,
+            admin_document_id: row.get(1)?,
+            version: row.get(2)?,
+            current: file_reference == current,
+            file_reference,
+            original_name: row.get(4)?,
+            note: row.get(5)?,
+            uploaded_date: row.get(6)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut versions = Vec::new();
+    for version in version_iter {
+        versions.push(version.map_err(|e| e.to_string())?);
+    }
+    Ok(versions)
+}
+
+#[command]
+pub fn restore_admin_doc_version(version_id: i64) -> Result<Vec<AdminDocumentVersion>, String> {
+    let conn = Connection::open("app.db").map_err(|e| e.to_string())?;
+    let (admin_document_id, file_reference, original_name): (i64, String, String) = conn
+        .query_row(
+            "SELECT admin_document_id, file_reference, original_name FROM admin_document_versions WHERE id = ?1",
+            params![version_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|_| format!("Version {} not found", version_id))?;
+
+    let added_date = chrono::Local::now().naive_local().date().to_string();
+    replace_primary_attachment(&conn, "admin_documents", admin_document_id, &file_reference, &original_name, &added_date)?;
+    get_admin_doc_versions(admin_document_id)
+}
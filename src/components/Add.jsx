@@ -11,6 +11,7 @@ const Add = () => {
   const [amount, setAmount] = useState('');
   const [dueDate, setDueDate] = useState('');
   const [path, setPath] = useState('');
+  const [reference, setReference] = useState('');
   const [description, setDescription] = useState('');
   const [status] = useState('Ouvert');  // Valeur par défaut "Open"
   const [paymentDate] = useState(''); // Valeur par défaut vide
@@ -36,6 +37,9 @@ const Add = () => {
   const [adminDocDescription, setAdminDocDescription] = useState('');
   const [adminDocStatus, setAdminDocStatus] = useState('');
   const [adminDocPath, setAdminDocPath] = useState('');
+  const [issueDate, setIssueDate] = useState('');
+  const [expiryDate, setExpiryDate] = useState('');
+  const [renewalLeadDays, setRenewalLeadDays] = useState('');
 
   const [adminDocumentsConcerns, setAdminDocumentsConcerns] = useState([]);
   const [adminDocumentsCategories, setAdminDocumentsCategories] = useState([]);
@@ -144,6 +148,24 @@ const Add = () => {
     }
   };
 
+  // Pré-remplit le formulaire avec les champs lus dans le fichier sélectionné (rien n'est enregistré)
+  const suggestInvoiceFields = async () => {
+    if (!path) {
+      alert('Select a file first');
+      return;
+    }
+    try {
+      const suggestion = await invoke('suggest_invoice_fields', { path });
+      if (suggestion.creditor) setCreditor(suggestion.creditor);
+      if (suggestion.amount !== null) setAmount(String(suggestion.amount));
+      if (suggestion.due_date) setDueDate(suggestion.due_date);
+      if (suggestion.reference) setReference(suggestion.reference);
+    } catch (error) {
+      console.error('Failed to read invoice', error);
+      alert(`Failed to read invoice: ${error}`);
+    }
+  };
+
   const addInvoice = async () => {
     try {
       const data = {
@@ -156,12 +178,22 @@ const Add = () => {
         description,
         status,
         payment_date: paymentDate,
+        reference: reference || null,
       };
 
       // Log des valeurs pour vérifier qu'elles sont correctement définies
       console.log("Data being sent to Rust:", data);
 
-      await invoke('add_invoice', data);
+      const result = await invoke('add_invoice', data);
+      if (result.duplicates.length > 0) {
+        const summary = result.duplicates
+          .map(({ invoice }) => `- ${invoice.creditor} ${invoice.amount} (${invoice.due_date})`)
+          .join('\n');
+        if (!window.confirm(`Possible duplicate invoice(s):\n${summary}\n\nAdd anyway?`)) {
+          return;
+        }
+        await invoke('add_invoice', { ...data, force: true });
+      }
       alert('Invoice added successfully');
     } catch (error) {
       console.error('Failed to add invoice', error);
@@ -199,6 +231,9 @@ const Add = () => {
         adminDocDescription,
         adminDocStatus,
         adminDocPath,
+        issueDate: issueDate || null,
+        expiryDate: expiryDate || null,
+        renewalLeadDays: renewalLeadDays ? parseInt(renewalLeadDays, 10) : null,
       };
 
       // Log des valeurs pour vérifier qu'elles sont correctement définies
@@ -273,7 +308,14 @@ const Add = () => {
                 readOnly
               />
               <button type="button" onClick={() => handleFileSelect('invoice')}>Select File</button>
+              <button type="button" onClick={suggestInvoiceFields}>Suggest Fields</button>
             </div>
+            <input
+              type="text"
+              placeholder="Reference"
+              value={reference}
+              onChange={(e) => setReference(e.target.value)}
+            />
             <input
               type="text"
               placeholder="Description"
@@ -397,6 +439,29 @@ const Add = () => {
               <option value="Archived">Archived</option>
             </select>
 
+            <input
+              type="date"
+              title="Issue date"
+              value={issueDate}
+              onChange={(e) => setIssueDate(e.target.value)}
+            />
+
+            <input
+              type="date"
+              title="Expiry date"
+              value={expiryDate}
+              onChange={(e) => setExpiryDate(e.target.value)}
+            />
+
+            <input
+              type="number"
+              min="0"
+              max="3650"
+              placeholder="Renewal lead time (days)"
+              value={renewalLeadDays}
+              onChange={(e) => setRenewalLeadDays(e.target.value)}
+            />
+
             <div className="path-container">
               <input
                 type="text"
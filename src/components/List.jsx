@@ -96,16 +96,23 @@ const List = () => {
     }
   };
 
-  const viewFile = async (path) => {
-    if (path) {
-      try {
-        await invoke('open_file', { path });
-      } catch (error) {
-        console.error('Failed to open file', error);
+  const viewFile = async (recordType, recordId) => {
+    try {
+      const attachments = await invoke('get_attachments', { recordType, recordId });
+      if (attachments.length === 0) {
+        alert('No file available');
+        return;
+      }
+      await invoke('open_attachment', { attachmentId: attachments[0].id });
+    } catch (error) {
+      console.error('Failed to open file', error);
+      if (error.kind === 'file_missing') {
+        alert(`File not found: ${error.original_name}`);
+      } else if (error.kind === 'not_allowed') {
+        alert(error.reason);
+      } else {
         alert('Failed to open file');
       }
-    } else {
-      alert('No file path available');
     }
   };
 
@@ -174,6 +181,16 @@ const List = () => {
     }
   };
 
+  const setAdminDocumentLifecycle = async (id, lifecycleStatus) => {
+    try {
+      const updated = await invoke('set_admin_doc_lifecycle', { id, lifecycleStatus });
+      setAdminDocuments(adminDocuments.map(adminDocument => adminDocument.id === id ? updated : adminDocument));
+    } catch (error) {
+      console.error('Failed to update admin document', error);
+      alert('Failed to update admin document');
+    }
+  };
+
   const filteredInvoices = applyFilters('invoices');
   const filteredRevenues = applyFilters('revenues');
   const filteredAdminDocuments = applyFilters('adminDocuments');
@@ -259,7 +276,7 @@ const List = () => {
                     <td>{invoice.due_date}</td>
                     <td>
                       {invoice.path && (
-                        <button title="View" onClick={() => viewFile(invoice.path)}>
+                        <button title="View" onClick={() => viewFile('invoices', invoice.id)}>
                           <FontAwesomeIcon icon={faFilePdf} />
                         </button>
                       )}
@@ -331,7 +348,7 @@ const List = () => {
                     <td>{revenue.revenue_description}</td>
                     <td>
                       {revenue.path && (
-                        <button title="View" onClick={() => viewFile(revenue.revenue_path)}>
+                        <button title="View" onClick={() => viewFile('revenues', revenue.id)}>
                           <FontAwesomeIcon icon={faFilePdf} />
                         </button>
                       )}
@@ -386,6 +403,8 @@ const List = () => {
                   <th>Category</th>
                   <th>Description</th>
                   <th>Status</th>
+                  <th>Lifecycle</th>
+                  <th>Expiry</th>
                   <th>Document</th>
                   <th>Actions</th>
                 </tr>
@@ -398,9 +417,21 @@ const List = () => {
                     <td>{adminDocument.admin_doc_category}</td>
                     <td>{adminDocument.admin_doc_description}</td>
                     <td>{adminDocument.admin_doc_status}</td>
+                    <td>
+                      <select
+                        value={adminDocument.lifecycle_status}
+                        onChange={(e) => setAdminDocumentLifecycle(adminDocument.id, e.target.value)}
+                      >
+                        <option value="received">Received</option>
+                        <option value="to_process">To process</option>
+                        <option value="filed">Filed</option>
+                        <option value="archived">Archived</option>
+                      </select>
+                    </td>
+                    <td>{adminDocument.expiry_date}</td>
                     <td>
                       {adminDocument.admin_doc_path && (
-                        <button title="View" onClick={() => viewFile(adminDocument.admin_doc_path)}>
+                        <button title="View" onClick={() => viewFile('admin_documents', adminDocument.id)}>
                           <FontAwesomeIcon icon={faFilePdf} />
                         </button>
                       )}
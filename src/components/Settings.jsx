@@ -110,6 +110,16 @@ const Settings = () => {
     }
   };
 
+  const setTaskStatusDone = async (id, isDone) => {
+    try {
+      await invoke('set_task_status_done', { id, isDone });
+      fetchTaskStatuses();
+    } catch (error) {
+      console.error('Failed to update task status', error);
+      alert('Failed to update task status');
+    }
+  };
+
   const deleteTaskCategory = async (id) => {
     try {
       await invoke('delete_task_category', { id });
@@ -601,6 +611,14 @@ const Settings = () => {
                 {taskStatuses.map((taskStatus) => (
                   <li key={taskStatus.id}>
                     {taskStatus.name}
+                    <label title="Tasks in this status count as done">
+                      <input
+                        type="checkbox"
+                        checked={taskStatus.is_done}
+                        onChange={(e) => setTaskStatusDone(taskStatus.id, e.target.checked)}
+                      />
+                      Done
+                    </label>
                     <button onClick={() => deleteTaskStatus(taskStatus.id)}>
                       <FontAwesomeIcon icon={faTrash} />
                     </button>
@@ -136,6 +136,16 @@ const ToDo = () => {
   };
   
 
+  const setTaskStatus = async (id, status) => {
+    try {
+      const updated = await invoke('set_task_status', { id, status });
+      setTasks(tasks.map(task => task.id === id ? updated : task));
+    } catch (error) {
+      console.error('Failed to update task status', error);
+      alert('Failed to update task status');
+    }
+  };
+
   const transformTasksToEvents = (tasks) => {
     return tasks.map((task) => ({
       id: task.id.toString(),
@@ -299,9 +309,18 @@ const ToDo = () => {
               <tbody>
                 {filteredTasks.map((task) => (
                   <tr key={task.id}>
-                    <td>{task.title}</td>
+                    <td>{task.title}{task.blocked && <span className='blocked' title='Waiting for other tasks'> (blocked)</span>}{task.recurrence && <span className='recurring' title={task.recurrence}> ↻</span>}</td>
                     <td>{task.description}</td>
-                    <td>{task.status}</td>
+                    <td>
+                      <select value={task.status} onChange={(e) => setTaskStatus(task.id, e.target.value)}>
+                        {!taskStatuses.some((status) => status.name === task.status) && (
+                          <option value={task.status}>{task.status}</option>
+                        )}
+                        {taskStatuses.map((status) => (
+                          <option key={status.id} value={status.name}>{status.name}</option>
+                        ))}
+                      </select>
+                    </td>
                     <td>{task.priority}</td>
                     <td>{task.due_date}</td>
                     <td>{task.creation_date}</td>